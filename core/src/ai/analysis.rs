@@ -0,0 +1,73 @@
+//! The solver's verdict on every legal move of a position, for studying positions.
+
+use std::fmt;
+
+use crate::board::Board;
+use crate::rules::Move;
+
+use super::{AiResult, AiState, Budget};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// Winnable in this many player moves, counting the move itself.
+    Winnable(usize),
+    Lost,
+    Unknown,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Verdict::Winnable(1) => write!(f, "wins"),
+            Verdict::Winnable(n) => write!(f, "winnable in {} moves", n),
+            Verdict::Lost => write!(f, "lost"),
+            Verdict::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Plays a move and its automatic moves, and searches the position after it. Fails like
+/// `Board::apply` if the move is not legal.
+pub fn evaluate(board: &Board, m: &Move, budget: &Budget) -> Result<Verdict, Move> {
+    let mut board = board.clone();
+    board.apply(m)?;
+    if board.is_won() {
+        return Ok(Verdict::Winnable(1));
+    }
+    Ok(match AiState::new(board.stacks().to_vec()).astar(budget) {
+        AiResult::Winable(path) => Verdict::Winnable(1 + board.player_moves(&path).len()),
+        AiResult::Lost => Verdict::Lost,
+        AiResult::Unknown => Verdict::Unknown,
+    })
+}
+
+/// Every legal move of the position with its verdict, each searched with the whole budget.
+pub fn analyse(board: &Board, budget: &Budget) -> Vec<(Move, Verdict)> {
+    board
+        .legal_moves()
+        .into_iter()
+        .map(|m| (m, evaluate(board, &m, budget).unwrap()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deal::Deal;
+
+    #[test]
+    fn every_move() {
+        let board = Board::new(&Deal::from_seed(5));
+        let verdicts = analyse(&board, &Budget::nodes(20_000));
+        let moves: Vec<_> = verdicts.iter().map(|&(m, _)| m).collect();
+        assert_eq!(moves, board.legal_moves());
+        assert!(
+            verdicts
+                .iter()
+                .any(|(_, v)| matches!(v, Verdict::Winnable(n) if *n > 1))
+        );
+
+        let bogus = Move::Cards(0, 1, 40);
+        assert_eq!(evaluate(&board, &bogus, &Budget::nodes(10)), Err(bogus));
+    }
+}
@@ -0,0 +1,96 @@
+//! Beam search: breadth first, keeping only the best states of every depth.
+//!
+//! The search can only prove a game lost if it never had to drop a state.
+
+use std::cmp::Reverse;
+use std::sync::atomic::AtomicUsize;
+use std::time::Instant;
+
+use crate::rules::Move;
+
+use super::{
+    AiResult, AiState, Budget, Limits, SearchStats, Solver, TranspositionTable, memory, path,
+};
+
+pub const DEFAULT_BEAM_WIDTH: usize = 500;
+
+pub struct Beam {
+    /// States kept per depth.
+    pub width: usize,
+}
+
+impl Default for Beam {
+    fn default() -> Beam {
+        Beam {
+            width: DEFAULT_BEAM_WIDTH,
+        }
+    }
+}
+
+impl Solver for Beam {
+    fn name(&self) -> &'static str {
+        "beam"
+    }
+
+    fn search(&self, state: &AiState, budget: &Budget) -> (AiResult, SearchStats) {
+        let start = Instant::now();
+        let expanded = AtomicUsize::new(0);
+        let limits = Limits::new(budget, &expanded);
+        let mut visited = budget.table(1);
+        let mut stats = SearchStats::default();
+        let finish = |mut stats: SearchStats, visited: &TranspositionTable| {
+            stats.table = visited.stats;
+            stats.elapsed = start.elapsed();
+            stats
+        };
+
+        // parent and move of every generated node, to reconstruct the path
+        let mut nodes: Vec<(usize, Option<Move>)> = vec![(0, None)];
+        let mut beam = vec![(state.clone(), 0)];
+        let mut dropped = false;
+
+        for depth in 0.. {
+            if beam.is_empty() {
+                break;
+            }
+            let mut next = Vec::new();
+            let open = beam.len();
+            stats.max_open = stats.max_open.max(open);
+            for (state, node) in beam {
+                let key = TranspositionTable::key(&state);
+                if visited.probe(key) {
+                    stats.duplicates += 1;
+                    continue;
+                }
+                if state.is_won() {
+                    let result = AiResult::Winable(path(&nodes, node));
+                    return (result, finish(stats, &visited));
+                }
+                if limits.exhausted(memory(&visited, open + next.len(), nodes.len())) {
+                    return (AiResult::Unknown, finish(stats, &visited));
+                }
+                stats.expand(&state, limits.weights);
+                visited.store(key, depth);
+
+                for (m, child) in state.successors(limits.weights) {
+                    nodes.push((node, Some(m)));
+                    next.push((child, nodes.len() - 1));
+                    stats.generated += 1;
+                }
+            }
+            if next.len() > self.width {
+                next.sort_by_cached_key(|(state, _)| Reverse(state.score(limits.weights)));
+                next.truncate(self.width);
+                dropped = true;
+            }
+            beam = next;
+        }
+
+        let result = if dropped {
+            AiResult::Unknown
+        } else {
+            AiResult::Lost
+        };
+        (result, finish(stats, &visited))
+    }
+}
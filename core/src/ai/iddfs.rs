@@ -0,0 +1,112 @@
+//! Depth first search with iterative deepening.
+//!
+//! Every iteration searches all paths up to a move limit, trying the best scores first, and raises
+//! the limit if no path won. Forced moves, like the automatic ones, are not counted. A state
+//! reached again at the same depth or deeper is not searched again within an iteration. The game is
+//! lost once an iteration ends below the limit.
+
+use std::sync::atomic::AtomicUsize;
+use std::time::Instant;
+
+use crate::rules::Move;
+
+use super::{AiResult, AiState, Budget, Limits, SearchStats, Solver, TranspositionTable, memory};
+
+/// Move limit of the first iteration. Solutions rarely take fewer moves.
+const FIRST_LIMIT: u32 = 40;
+/// How much each iteration raises the limit.
+const LIMIT_STEP: u32 = 20;
+
+pub struct Iddfs;
+
+/// One iteration.
+struct Dfs<'a> {
+    limit: u32,
+    visited: TranspositionTable,
+    limits: &'a Limits<'a>,
+    stats: &'a mut SearchStats,
+    path: Vec<Move>,
+    /// Whether a path was cut off by the limit.
+    cut: bool,
+}
+
+impl Dfs<'_> {
+    /// Whether the state leads to a win, None if the budget ran out.
+    fn visit(&mut self, state: &AiState, depth: u32) -> Option<bool> {
+        if state.is_won() {
+            return Some(true);
+        }
+        if depth == self.limit {
+            self.cut = true;
+            return Some(false);
+        }
+        let key = TranspositionTable::key(state);
+        if self.visited.depth(key).is_some_and(|d| d <= depth) {
+            self.stats.duplicates += 1;
+            return Some(false);
+        }
+        // the states on the path are the open ones
+        if self
+            .limits
+            .exhausted(memory(&self.visited, self.path.len(), self.path.len()))
+        {
+            return None;
+        }
+        self.stats.expand(state, self.limits.weights);
+        self.stats.max_open = self.stats.max_open.max(self.path.len() + 1);
+        self.visited.store(key, depth);
+
+        let successors = state.successors(self.limits.weights);
+        self.stats.generated += successors.len();
+        // forced moves do not count against the limit
+        let depth = if successors.len() == 1 {
+            depth
+        } else {
+            depth + 1
+        };
+        for (m, next) in successors {
+            self.path.push(m);
+            if self.visit(&next, depth)? {
+                return Some(true);
+            }
+            self.path.pop();
+        }
+        Some(false)
+    }
+}
+
+impl Solver for Iddfs {
+    fn name(&self) -> &'static str {
+        "iddfs"
+    }
+
+    fn search(&self, state: &AiState, budget: &Budget) -> (AiResult, SearchStats) {
+        let start = Instant::now();
+        let expanded = AtomicUsize::new(0);
+        let limits = Limits::new(budget, &expanded);
+        let mut stats = SearchStats::default();
+
+        let mut limit = FIRST_LIMIT;
+        let result = loop {
+            let mut dfs = Dfs {
+                limit,
+                visited: budget.table(1),
+                limits: &limits,
+                stats: &mut stats,
+                path: Vec::new(),
+                cut: false,
+            };
+            let won = dfs.visit(state, 0);
+            let (table, path, cut) = (dfs.visited.stats, dfs.path, dfs.cut);
+            stats.table += table;
+            match won {
+                None => break AiResult::Unknown,
+                Some(true) => break AiResult::Winable(path),
+                Some(false) if !cut => break AiResult::Lost,
+                Some(false) => limit += LIMIT_STEP,
+            }
+        };
+        stats.elapsed = start.elapsed();
+        (result, stats)
+    }
+}
@@ -0,0 +1,385 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::board::N_STACKS;
+use crate::deal::DECK_SIZE;
+use crate::rules::{self, Move};
+use crate::types::{Stack, StackRole, Suite};
+
+mod analysis;
+mod beam;
+mod iddfs;
+mod monte_carlo;
+mod parallel;
+mod solver;
+mod table;
+mod weights;
+
+pub use self::analysis::{Verdict, analyse, evaluate};
+pub use self::beam::{Beam, DEFAULT_BEAM_WIDTH};
+pub use self::iddfs::Iddfs;
+pub use self::monte_carlo::MonteCarlo;
+pub use self::solver::{BestFirst, Report, SOLVERS, Solver, solver};
+pub use self::table::{TableStats, TranspositionTable};
+pub use self::weights::{WEIGHT_NAMES, Weights, WeightsError};
+
+/// Memory for the transposition table of a search, unless the budget says otherwise.
+pub const DEFAULT_TABLE_BYTES: usize = 64 << 20;
+/// Memory of a search, unless the budget says otherwise. The open states are not bounded by
+/// anything else.
+pub const DEFAULT_MEMORY_BYTES: usize = 512 << 20;
+
+pub enum AiResult {
+    Unknown,
+    Winable(Vec<Move>),
+    Lost,
+}
+
+/// Memory a state takes, including its cards, to estimate the memory of a search.
+const STATE_BYTES: usize = mem::size_of::<AiState>()
+    + N_STACKS * mem::size_of::<Stack>()
+    + DECK_SIZE * mem::size_of::<Suite>();
+
+/// Limits for a search. It gives up with `AiResult::Unknown` as soon as one of them is used up.
+#[derive(Clone, Debug)]
+pub struct Budget {
+    pub nodes: usize,
+    pub time: Option<Duration>,
+    /// Memory for the open states, the paths and the table of visited states, estimated from
+    /// their sizes.
+    pub memory: Option<usize>,
+    /// Memory for the table of visited states. A smaller table forgets states, which costs
+    /// nodes but not correctness. At most half the memory budget.
+    pub table_bytes: usize,
+    /// Threads that search at once, sharing the budget. Any number finds a solution if one
+    /// exists within the budget, though not always the same one, see `parallel`.
+    pub threads: usize,
+    /// What the search scores states with.
+    pub weights: Weights,
+    /// Gives up as soon as this is set, for searches whose result is no longer needed.
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Budget {
+    pub fn nodes(nodes: usize) -> Budget {
+        Budget {
+            nodes,
+            time: None,
+            memory: Some(DEFAULT_MEMORY_BYTES),
+            table_bytes: DEFAULT_TABLE_BYTES,
+            threads: 1,
+            weights: Weights::DEFAULT,
+            cancel: None,
+        }
+    }
+
+    /// A budget of wall clock time only.
+    pub fn time(time: Duration) -> Budget {
+        Budget {
+            time: Some(time),
+            ..Budget::nodes(usize::MAX)
+        }
+    }
+
+    /// A table for one of `parts` searches that share the budget.
+    fn table(&self, parts: usize) -> TranspositionTable {
+        let bytes = self
+            .memory
+            .map_or(self.table_bytes, |m| self.table_bytes.min(m / 2));
+        TranspositionTable::new(bytes / parts, self.nodes)
+    }
+}
+
+/// What a search did.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub expanded: usize,
+    pub generated: usize,
+    /// Most states waiting to be expanded at once, in any one thread.
+    pub max_open: usize,
+    /// Generated states that had been expanded before.
+    pub duplicates: usize,
+    /// Best score of an expanded state.
+    pub best_score: Option<i32>,
+    pub elapsed: Duration,
+    pub table: TableStats,
+}
+
+impl SearchStats {
+    fn expand(&mut self, state: &AiState, weights: &Weights) {
+        self.expanded += 1;
+        self.best_score = self.best_score.max(Some(state.score(weights)));
+    }
+
+    /// Adds the stats of a search that ran at the same time.
+    fn merge(&mut self, other: &SearchStats) {
+        self.expanded += other.expanded;
+        self.generated += other.generated;
+        self.max_open = self.max_open.max(other.max_open);
+        self.duplicates += other.duplicates;
+        self.best_score = self.best_score.max(other.best_score);
+        self.elapsed = self.elapsed.max(other.elapsed);
+        self.table += other.table;
+    }
+}
+
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes expanded, {} generated, {} open at most, {} duplicates, ",
+            self.expanded, self.generated, self.max_open, self.duplicates
+        )?;
+        if let Some(score) = self.best_score {
+            write!(f, "best score {}, ", score)?;
+        }
+        write!(f, "{:.3}s", self.elapsed.as_secs_f64())
+    }
+}
+
+#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+pub struct AiState {
+    stacks: Vec<Stack>,
+}
+
+impl AiState {
+    pub fn new(stacks: Vec<Stack>) -> AiState {
+        AiState { stacks }
+    }
+
+    /// The path of a winnable game contains the automatic moves, see `Board::player_moves`.
+    pub fn astar(&self, budget: &Budget) -> AiResult {
+        self.astar_with_stats(budget).0
+    }
+
+    pub fn astar_with_stats(&self, budget: &Budget) -> (AiResult, SearchStats) {
+        if budget.threads > 1 {
+            return self.parallel(budget);
+        }
+        let start = Instant::now();
+        let mut visited = budget.table(1);
+        let expanded = AtomicUsize::new(0);
+        let limits = Limits::new(budget, &expanded);
+        let mut stats = SearchStats::default();
+        let result = self.best_first(0, &mut visited, &limits, &mut stats);
+        stats.table = visited.stats;
+        stats.elapsed = start.elapsed();
+        (result, stats)
+    }
+
+    /// Best first search from a state `depth` moves below the root of the whole search.
+    fn best_first(
+        &self,
+        depth: u32,
+        visited: &mut TranspositionTable,
+        limits: &Limits,
+        stats: &mut SearchStats,
+    ) -> AiResult {
+        // parent and move of every generated node, to reconstruct the path
+        let mut nodes: Vec<(usize, Option<Move>)> = vec![(0, None)];
+
+        let mut queue = BinaryHeap::new();
+        queue.push(Open::new(depth, self.clone(), 0, limits.weights));
+
+        while let Some(Open {
+            depth, state, node, ..
+        }) = queue.pop()
+        {
+            let key = TranspositionTable::key(&state);
+            if visited.probe(key) {
+                stats.duplicates += 1;
+                continue;
+            }
+
+            if state.is_won() {
+                return AiResult::Winable(path(&nodes, node));
+            }
+
+            if limits.exhausted(memory(visited, queue.len(), nodes.len())) {
+                return AiResult::Unknown;
+            }
+            stats.expand(&state, limits.weights);
+
+            visited.store(key, depth);
+
+            let moves = rules::calc_possible_moves(state.stacks.iter());
+            stats.generated += moves.len();
+            for m in moves {
+                let newstate = state.apply_move(m);
+                nodes.push((node, Some(m)));
+                queue.push(Open::new(
+                    depth + 1,
+                    newstate,
+                    nodes.len() - 1,
+                    limits.weights,
+                ));
+            }
+            stats.max_open = stats.max_open.max(queue.len());
+        }
+        AiResult::Lost
+    }
+
+    /// The states after every possible move, in the order the best first search tries them.
+    fn successors(&self, weights: &Weights) -> Vec<(Move, AiState)> {
+        // the queue pops the best score first, and the last generated of equal scores
+        let mut children: Vec<_> = rules::calc_possible_moves(self.stacks.iter())
+            .into_iter()
+            .enumerate()
+            .map(|(i, m)| {
+                let state = self.apply_move(m);
+                (state.score(weights), i, m, state)
+            })
+            .collect();
+        children.sort_by_key(|&(score, i, _, _)| Reverse((score, i)));
+        children.into_iter().map(|(_, _, m, s)| (m, s)).collect()
+    }
+
+    fn is_won(&self) -> bool {
+        rules::check_victory(self.stacks.iter())
+    }
+
+    fn apply_move(&self, m: Move) -> AiState {
+        let mut state = self.clone();
+        rules::apply_move(&mut state.stacks, &m);
+        state
+    }
+
+    fn score(&self, w: &Weights) -> i32 {
+        let mut score = 0;
+        for stack in &self.stacks {
+            match stack.role {
+                StackRole::Dragon => {
+                    if let Some(Suite::FaceDown) = stack.top() {
+                        score += w.collapsed_dragons
+                    }
+                }
+                StackRole::Target => {
+                    if let Some(Suite::Number(n, _)) = stack.top() {
+                        score += w.foundation_rank * n as i32
+                    }
+                }
+                StackRole::Sorting => score += w.sorting_run * stack.score(),
+                _ => {}
+            }
+        }
+        score
+    }
+}
+
+/// When a search has to give up.
+struct Limits<'a> {
+    nodes: usize,
+    memory: Option<usize>,
+    deadline: Option<Instant>,
+    expanded: &'a AtomicUsize,
+    stop: &'a (dyn Fn() -> bool + Sync),
+    cancel: Option<&'a AtomicBool>,
+    weights: &'a Weights,
+}
+
+impl<'a> Limits<'a> {
+    /// The limits of a single threaded search.
+    fn new(budget: &'a Budget, expanded: &'a AtomicUsize) -> Limits<'a> {
+        Limits {
+            nodes: budget.nodes,
+            memory: budget.memory,
+            // a deadline too far out for an Instant is no deadline
+            deadline: budget.time.and_then(|t| Instant::now().checked_add(t)),
+            expanded,
+            stop: &|| false,
+            cancel: budget.cancel.as_deref(),
+            weights: &budget.weights,
+        }
+    }
+
+    /// Counts an expanded node, true if the search must give up instead. `memory` is the
+    /// estimated memory the search uses.
+    fn exhausted(&self, memory: usize) -> bool {
+        let expanded = self.expanded.fetch_add(1, Ordering::Relaxed) + 1;
+        expanded >= self.nodes
+            || self.memory.is_some_and(|m| memory >= m)
+            || self.deadline.is_some_and(|d| Instant::now() >= d)
+            || (self.stop)()
+            || self.cancel.is_some_and(|c| c.load(Ordering::Relaxed))
+    }
+}
+
+/// Estimated memory of a search with a table, open states and nodes of the path tree.
+fn memory(table: &TranspositionTable, states: usize, nodes: usize) -> usize {
+    table.memory() + states * STATE_BYTES + nodes * mem::size_of::<(usize, Option<Move>)>()
+}
+
+fn path(nodes: &[(usize, Option<Move>)], mut node: usize) -> Vec<Move> {
+    let mut moves = Vec::new();
+    while let (parent, Some(m)) = nodes[node] {
+        moves.push(m);
+        node = parent;
+    }
+    moves.reverse();
+    moves
+}
+
+/// A state waiting in the queue of the best first search, which pops the deepest first, then
+/// the best score, then the last generated.
+struct Open {
+    depth: u32,
+    score: i32,
+    state: AiState,
+    node: usize,
+}
+
+impl Open {
+    fn new(depth: u32, state: AiState, node: usize, weights: &Weights) -> Open {
+        Open {
+            depth,
+            score: state.score(weights),
+            state,
+            node,
+        }
+    }
+
+    fn key(&self) -> (u32, i32, usize) {
+        (self.depth, self.score, self.node)
+    }
+}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> ::std::cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<::std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Open {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Open {}
+
+impl Stack {
+    fn score(&self) -> i32 {
+        let a = self.cards.iter();
+        let b = self.cards.iter().skip(1);
+        let mut score = 0;
+        for (l, u) in a.zip(b).rev() {
+            if rules::is_valid_pair(*l, *u) {
+                score += 1;
+            } else {
+                return score;
+            }
+        }
+        score
+    }
+}
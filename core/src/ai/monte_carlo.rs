@@ -0,0 +1,84 @@
+//! Randomized rollouts from the root.
+//!
+//! Every rollout plays moves until it wins or gets stuck, taking the best scored move half of
+//! the time and a random one otherwise, and never returning to a state it has seen. Rollouts can
+//! find wins but never prove a game lost.
+
+use std::collections::HashSet;
+use std::mem;
+use std::sync::atomic::AtomicUsize;
+use std::time::Instant;
+
+use crate::deal::SplitMix64;
+
+use super::{
+    AiResult, AiState, Budget, Limits, STATE_BYTES, SearchStats, Solver, TranspositionTable,
+};
+
+/// Moves after which a rollout gives up.
+const MAX_ROLLOUT: usize = 500;
+
+pub struct MonteCarlo {
+    /// Seed of the random moves. The same seed plays the same rollouts.
+    pub seed: u64,
+}
+
+impl Default for MonteCarlo {
+    fn default() -> MonteCarlo {
+        MonteCarlo { seed: 1 }
+    }
+}
+
+impl Solver for MonteCarlo {
+    fn name(&self) -> &'static str {
+        "montecarlo"
+    }
+
+    fn search(&self, state: &AiState, budget: &Budget) -> (AiResult, SearchStats) {
+        let start = Instant::now();
+        let expanded = AtomicUsize::new(0);
+        let limits = Limits::new(budget, &expanded);
+        let mut stats = SearchStats::default();
+        let mut rng = SplitMix64::new(self.seed);
+
+        let result = if !state.is_won() && state.successors(limits.weights).is_empty() {
+            AiResult::Lost
+        } else {
+            'rollouts: loop {
+                let mut state = state.clone();
+                let mut path = Vec::new();
+                let mut seen = HashSet::from([TranspositionTable::key(&state)]);
+                while path.len() < MAX_ROLLOUT {
+                    if state.is_won() {
+                        break 'rollouts AiResult::Winable(path);
+                    }
+                    // the states of a rollout are all that is kept
+                    if limits.exhausted(seen.len() * (STATE_BYTES + mem::size_of::<u64>())) {
+                        break 'rollouts AiResult::Unknown;
+                    }
+                    stats.expand(&state, limits.weights);
+                    let mut moves = state.successors(limits.weights);
+                    stats.generated += moves.len();
+                    let before = moves.len();
+                    moves.retain(|(_, s)| !seen.contains(&TranspositionTable::key(s)));
+                    stats.duplicates += before - moves.len();
+                    stats.max_open = stats.max_open.max(moves.len());
+                    if moves.is_empty() {
+                        break;
+                    }
+                    let i = if rng.below(2) == 0 {
+                        0
+                    } else {
+                        rng.below(moves.len())
+                    };
+                    let (m, next) = moves.swap_remove(i);
+                    seen.insert(TranspositionTable::key(&next));
+                    path.push(m);
+                    state = next;
+                }
+            }
+        };
+        stats.elapsed = start.elapsed();
+        (result, stats)
+    }
+}
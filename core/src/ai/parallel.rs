@@ -0,0 +1,206 @@
+//! Searching one position with several threads.
+//!
+//! The best first search always expands the deepest open state, which makes it a depth first
+//! search that tries the moves of a state in the order of their scores. The tree is split into
+//! subtrees a few moves below the root, listed in the order the single threaded search would
+//! enter them, and the threads take the subtrees from that list. Each subtree gets its own table
+//! of visited states, knowing only the states on its way from the root.
+//!
+//! Every subtree is searched completely unless the budget runs out, so the search finds a solution
+//! if one exists within the budget, and the deal is lost if every subtree ends lost. The solution
+//! is not always the one a single thread finds: the moves can go in circles, and the states a
+//! single thread remembers from earlier subtrees change what it reaches in later ones. The first
+//! subtree in the list that wins decides the result, and the subtrees after it are given up.
+//!
+//! The threads share the node budget and split the memory budget, so `Budget::nodes` caps the
+//! work of all of them together.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+use crate::rules::Move;
+
+use super::{
+    AiResult, AiState, Budget, DEFAULT_MEMORY_BYTES, Limits, SearchStats, TranspositionTable,
+    Weights,
+};
+
+/// Subtrees per thread, so that threads that finish early find more work.
+const UNITS_PER_THREAD: usize = 8;
+/// Moves below the root after which the tree is not split any further.
+const MAX_SPLIT_DEPTH: u32 = 4;
+
+/// A subtree of the search.
+struct Unit {
+    state: AiState,
+    depth: u32,
+    /// Moves from the root.
+    moves: Vec<Move>,
+    /// States on the way from the root, which the search must not enter again.
+    ancestors: Vec<u64>,
+}
+
+impl AiState {
+    /// Subtrees below this state, in the order the single threaded search enters them.
+    fn split(&self, units: usize, weights: &Weights) -> (Vec<Unit>, usize) {
+        let mut frontier = vec![Unit {
+            state: self.clone(),
+            depth: 0,
+            moves: Vec::new(),
+            ancestors: Vec::new(),
+        }];
+        let mut expanded = 0;
+
+        for _ in 0..MAX_SPLIT_DEPTH {
+            if frontier.len() >= units {
+                break;
+            }
+            let mut next = Vec::new();
+            // a state that appears twice on one level is searched the first time
+            let mut seen = HashSet::new();
+            for unit in frontier {
+                if unit.state.is_won() {
+                    next.push(unit);
+                    continue;
+                }
+                expanded += 1;
+
+                let mut ancestors = unit.ancestors.clone();
+                ancestors.push(TranspositionTable::key(&unit.state));
+                for (m, state) in unit.state.successors(weights) {
+                    let key = TranspositionTable::key(&state);
+                    if ancestors.contains(&key) || !seen.insert(key) {
+                        continue;
+                    }
+                    let mut moves = unit.moves.clone();
+                    moves.push(m);
+                    next.push(Unit {
+                        state,
+                        depth: unit.depth + 1,
+                        moves,
+                        ancestors: ancestors.clone(),
+                    });
+                }
+            }
+            frontier = next;
+        }
+        (frontier, expanded)
+    }
+
+    pub(super) fn parallel(&self, budget: &Budget) -> (AiResult, SearchStats) {
+        let start = Instant::now();
+        let deadline = budget.time.and_then(|t| start.checked_add(t));
+        let (units, split) = self.split(budget.threads * UNITS_PER_THREAD, &budget.weights);
+
+        let next = AtomicUsize::new(0);
+        let expanded = AtomicUsize::new(split);
+        let memory = budget.memory.unwrap_or(DEFAULT_MEMORY_BYTES) / budget.threads;
+        // the first subtree known to win, later ones are not needed
+        let decided = AtomicUsize::new(usize::MAX);
+        let results: Vec<Mutex<Option<(AiResult, SearchStats)>>> =
+            units.iter().map(|_| Mutex::new(None)).collect();
+
+        thread::scope(|scope| {
+            for _ in 0..budget.threads {
+                scope.spawn(|| {
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        if i >= units.len() || i > decided.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        let unit = &units[i];
+                        let mut visited = budget.table(budget.threads);
+                        for &key in &unit.ancestors {
+                            visited.store(key, 0);
+                        }
+                        let limits = Limits {
+                            nodes: budget.nodes,
+                            memory: Some(memory),
+                            deadline,
+                            expanded: &expanded,
+                            stop: &|| decided.load(Ordering::Relaxed) < i,
+                            cancel: budget.cancel.as_deref(),
+                            weights: &budget.weights,
+                        };
+                        let mut stats = SearchStats::default();
+                        let result =
+                            unit.state
+                                .best_first(unit.depth, &mut visited, &limits, &mut stats);
+                        if matches!(result, AiResult::Winable(_)) {
+                            decided.fetch_min(i, Ordering::Relaxed);
+                        }
+                        stats.table = visited.stats;
+                        *results[i].lock().unwrap() = Some((result, stats));
+                    }
+                });
+            }
+        });
+
+        let mut result = AiResult::Lost;
+        let mut stats = SearchStats {
+            expanded: split,
+            ..SearchStats::default()
+        };
+        for (unit, r) in units.into_iter().zip(results) {
+            let Some((r, unit_stats)) = r.into_inner().unwrap() else {
+                continue;
+            };
+            stats.merge(&unit_stats);
+            match (&result, r) {
+                (AiResult::Winable(_), _) | (_, AiResult::Lost) => {}
+                (_, AiResult::Winable(path)) => {
+                    let mut moves = unit.moves;
+                    moves.extend(path);
+                    result = AiResult::Winable(moves);
+                }
+                (_, AiResult::Unknown) => result = AiResult::Unknown,
+            }
+        }
+        stats.elapsed = start.elapsed();
+        (result, stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::deal::Deal;
+
+    fn solve(seed: u64, nodes: usize, threads: usize) -> Result<Vec<Move>, &'static str> {
+        let board = Board::new(&Deal::from_seed(seed));
+        let budget = Budget {
+            threads,
+            ..Budget::nodes(nodes)
+        };
+        match AiState::new(board.stacks().to_vec()).astar(&budget) {
+            AiResult::Winable(path) => Ok(path),
+            AiResult::Lost => Err("lost"),
+            AiResult::Unknown => Err("unknown"),
+        }
+    }
+
+    fn replays_to_win(seed: u64, path: &[Move]) -> bool {
+        let mut board = Board::new(&Deal::from_seed(seed));
+        for m in board.player_moves(path) {
+            board.apply(&m).unwrap();
+        }
+        board.is_won()
+    }
+
+    #[test]
+    fn finds_a_solution() {
+        for threads in [1, 2, 4] {
+            let path = solve(12, 200_000, threads).unwrap();
+            assert!(replays_to_win(12, &path), "{} threads", threads);
+            // the threads share the budget, so a deal that takes many nodes may run out of it
+            if let Ok(path) = solve(29, 200_000, threads) {
+                assert!(replays_to_win(29, &path), "{} threads", threads);
+            }
+            assert_eq!(solve(1, 500, threads), Err("unknown"));
+        }
+    }
+}
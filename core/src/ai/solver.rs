@@ -0,0 +1,139 @@
+//! Search strategies behind a common interface, so they can be compared on the same deals.
+
+use super::{AiResult, AiState, Beam, Budget, Iddfs, MonteCarlo, SearchStats};
+
+/// Names of the solvers, for `solver`. The first one is the default.
+pub const SOLVERS: [&str; 4] = ["astar", "iddfs", "beam", "montecarlo"];
+
+/// What a solver found, and how much of the budget it took.
+pub struct Report {
+    pub result: AiResult,
+    pub stats: SearchStats,
+}
+
+pub trait Solver: Sync {
+    fn name(&self) -> &'static str;
+
+    /// Searches for a win, giving up with `AiResult::Unknown` when the budget is used up. The
+    /// path of a winnable game contains the automatic moves, see `Board::player_moves`.
+    fn search(&self, state: &AiState, budget: &Budget) -> (AiResult, SearchStats);
+
+    fn solve(&self, state: &AiState, budget: &Budget) -> Report {
+        let (result, stats) = self.search(state, budget);
+        Report { result, stats }
+    }
+}
+
+/// The search of `AiState::astar`.
+pub struct BestFirst;
+
+impl Solver for BestFirst {
+    fn name(&self) -> &'static str {
+        "astar"
+    }
+
+    fn search(&self, state: &AiState, budget: &Budget) -> (AiResult, SearchStats) {
+        state.astar_with_stats(budget)
+    }
+}
+
+/// The solver with one of the `SOLVERS` names, with its default settings.
+pub fn solver(name: &str) -> Option<Box<dyn Solver>> {
+    Some(match name {
+        "astar" => Box::new(BestFirst),
+        "iddfs" => Box::new(Iddfs),
+        "beam" => Box::new(Beam::default()),
+        "montecarlo" => Box::new(MonteCarlo::default()),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::deal::Deal;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use std::time::Duration;
+
+    #[test]
+    fn all_solvers() {
+        for name in SOLVERS {
+            let solver = solver(name).unwrap();
+            assert_eq!(solver.name(), name);
+            let mut won = 0;
+            for seed in [5, 10] {
+                let board = Board::new(&Deal::from_seed(seed));
+                let state = AiState::new(board.stacks().to_vec());
+                let report = solver.solve(&state, &Budget::nodes(20_000));
+                assert!(report.stats.expanded > 0);
+                if let AiResult::Winable(path) = report.result {
+                    let mut board = board.clone();
+                    for m in board.player_moves(&path) {
+                        board.apply(&m).unwrap();
+                    }
+                    assert!(board.is_won(), "{} on deal {}", name, seed);
+                    won += 1;
+                }
+            }
+            assert!(won > 0, "{} did not solve any deal", name);
+        }
+        assert!(solver("dijkstra").is_none());
+    }
+
+    #[test]
+    fn budget() {
+        let board = Board::new(&Deal::from_seed(3));
+        let state = AiState::new(board.stacks().to_vec());
+        for name in SOLVERS {
+            let solver = solver(name).unwrap();
+            let report = solver.solve(&state, &Budget::time(Duration::ZERO));
+            assert!(matches!(report.result, AiResult::Unknown), "{}", name);
+            assert_eq!(report.stats.expanded, 0, "{}", name);
+        }
+
+        // a time limit too long for an Instant
+        let forever = Budget {
+            time: Some(Duration::MAX),
+            ..Budget::nodes(100)
+        };
+        for name in SOLVERS {
+            let report = solver(name).unwrap().solve(&state, &forever);
+            assert!(matches!(report.result, AiResult::Unknown), "{}", name);
+        }
+        let threads = Budget {
+            threads: 2,
+            ..forever
+        };
+        assert!(matches!(state.astar(&threads), AiResult::Unknown));
+
+        let cancelled = Budget {
+            cancel: Some(Arc::new(AtomicBool::new(true))),
+            ..Budget::nodes(1_000_000)
+        };
+        for name in SOLVERS {
+            let report = solver(name).unwrap().solve(&state, &cancelled);
+            assert!(matches!(report.result, AiResult::Unknown), "{}", name);
+        }
+        let threads = Budget {
+            threads: 2,
+            ..cancelled
+        };
+        assert!(matches!(state.astar(&threads), AiResult::Unknown));
+
+        // the searches that keep many states open
+        for name in ["astar", "beam"] {
+            let memory = Budget {
+                memory: Some(1 << 20),
+                ..Budget::nodes(1_000_000)
+            };
+            let report = solver(name).unwrap().solve(&state, &memory);
+            assert!(matches!(report.result, AiResult::Unknown), "{}", name);
+            let stats = report.stats;
+            assert!(stats.expanded < 10_000, "{}", name);
+            assert!(stats.generated >= stats.expanded, "{}", name);
+            assert!(stats.max_open > 0 && stats.best_score.is_some(), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! A fixed size transposition table for the searches.
+//!
+//! States are stored as 64 bit hashes in buckets of two entries. The first entry of a bucket
+//! keeps the state closest to the root, the second always takes the newest state. A full table
+//! forgets states instead of growing, so a search may expand a state twice but never runs out of
+//! memory. Two states with the same hash are taken for the same state.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::AddAssign;
+
+#[derive(Copy, Clone, Default)]
+struct Entry {
+    // 0 for an empty entry
+    key: u64,
+    depth: u32,
+}
+
+type Bucket = [Entry; 2];
+
+/// How the table was used during a search.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TableStats {
+    pub probes: usize,
+    pub hits: usize,
+    pub stores: usize,
+    /// Stores that pushed out another state.
+    pub replaced: usize,
+}
+
+impl TableStats {
+    pub fn hit_rate(&self) -> f64 {
+        if self.probes == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.probes as f64
+        }
+    }
+}
+
+impl AddAssign for TableStats {
+    fn add_assign(&mut self, other: TableStats) {
+        self.probes += other.probes;
+        self.hits += other.hits;
+        self.stores += other.stores;
+        self.replaced += other.replaced;
+    }
+}
+
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    pub stats: TableStats,
+}
+
+impl TranspositionTable {
+    /// A table that uses at most `bytes` of memory, but no more than needed for `entries`. A
+    /// bucket per entry leaves room for the entries that hash to the same bucket.
+    pub fn new(bytes: usize, entries: usize) -> TranspositionTable {
+        let max = (bytes / mem::size_of::<Bucket>()).max(1);
+        let wanted = entries
+            .max(1)
+            .checked_next_power_of_two()
+            .unwrap_or(usize::MAX);
+        // a power of two, so the index is a mask of the key
+        let n = if wanted <= max {
+            wanted
+        } else {
+            1 << max.ilog2()
+        };
+        TranspositionTable {
+            buckets: vec![Bucket::default(); n],
+            stats: TableStats::default(),
+        }
+    }
+
+    pub fn key<T: Hash>(state: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        state.hash(&mut hasher);
+        hasher.finish().max(1)
+    }
+
+    pub fn memory(&self) -> usize {
+        self.buckets.len() * mem::size_of::<Bucket>()
+    }
+
+    fn bucket(&mut self, key: u64) -> &mut Bucket {
+        let mask = self.buckets.len() as u64 - 1;
+        &mut self.buckets[(key & mask) as usize]
+    }
+
+    /// Whether the state was stored.
+    pub fn probe(&mut self, key: u64) -> bool {
+        self.stats.probes += 1;
+        let hit = self.bucket(key).iter().any(|e| e.key == key);
+        if hit {
+            self.stats.hits += 1;
+        }
+        hit
+    }
+
+    /// The depth the state was stored with.
+    pub fn depth(&mut self, key: u64) -> Option<u32> {
+        self.stats.probes += 1;
+        let depth = self
+            .bucket(key)
+            .iter()
+            .find(|e| e.key == key)
+            .map(|e| e.depth);
+        if depth.is_some() {
+            self.stats.hits += 1;
+        }
+        depth
+    }
+
+    /// Stores a state found `depth` moves from the root, or updates a stored state that was
+    /// found further from the root.
+    pub fn store(&mut self, key: u64, depth: u32) {
+        let bucket = self.bucket(key);
+        if let Some(e) = bucket.iter_mut().find(|e| e.key == key) {
+            e.depth = e.depth.min(depth);
+            return;
+        }
+        let (slot, replaced) = if bucket[0].key == 0 {
+            (0, false)
+        } else if depth <= bucket[0].depth {
+            // the deeper state moves to the always replaced entry
+            let replaced = bucket[1].key != 0;
+            bucket[1] = bucket[0];
+            (0, replaced)
+        } else {
+            (1, bucket[1].key != 0)
+        };
+        bucket[slot] = Entry { key, depth };
+        self.stats.stores += 1;
+        if replaced {
+            self.stats.replaced += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded() {
+        let mut table = TranspositionTable::new(1 << 10, 1_000_000);
+        assert_eq!(table.memory(), 1 << 10);
+        for key in 1..=10_000u64 {
+            table.store(key, (key % 7) as u32);
+        }
+        assert_eq!(table.memory(), 1 << 10);
+        assert_eq!(table.stats.stores, 10_000);
+        assert!(table.stats.replaced > 9_000);
+        assert!(table.probe(10_000));
+
+        // the entry closest to the root survives newer states
+        let mut table = TranspositionTable::new(64, 1);
+        table.store(1, 0);
+        for key in 2..100 {
+            table.store(key, 5);
+        }
+        assert!(table.probe(1));
+        assert!(table.probe(99));
+        assert!(!table.probe(50));
+        assert_eq!(table.stats.hit_rate(), 2.0 / 3.0);
+    }
+
+    #[test]
+    fn sized_by_entries() {
+        let table = TranspositionTable::new(1 << 30, 1000);
+        assert_eq!(table.memory(), 1024 * mem::size_of::<Bucket>());
+    }
+}
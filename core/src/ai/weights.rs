@@ -0,0 +1,136 @@
+//! The weights the searches score states with, and their text format.
+//!
+//! A weights file has one `name value` line per weight, and comments starting with a hash.
+//! Weights that are not in the file keep their default.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Weight of every feature of a state. Higher scores are searched first.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Weights {
+    /// Per dragon cell holding collapsed dragons.
+    pub collapsed_dragons: i32,
+    /// Per rank of the top card of every foundation.
+    pub foundation_rank: i32,
+    /// Per card that fits on the one below it, in the run on top of every sorting stack.
+    pub sorting_run: i32,
+}
+
+pub const WEIGHT_NAMES: [&str; 3] = ["collapsed_dragons", "foundation_rank", "sorting_run"];
+
+impl Weights {
+    /// The weights searches use unless told otherwise, usable in constants.
+    pub const DEFAULT: Weights = Weights {
+        collapsed_dragons: 100,
+        foundation_rank: 10,
+        sorting_run: 1,
+    };
+}
+
+impl Default for Weights {
+    fn default() -> Weights {
+        Weights::DEFAULT
+    }
+}
+
+impl From<[i32; 3]> for Weights {
+    fn from(w: [i32; 3]) -> Weights {
+        Weights {
+            collapsed_dragons: w[0],
+            foundation_rank: w[1],
+            sorting_run: w[2],
+        }
+    }
+}
+
+impl From<Weights> for [i32; 3] {
+    fn from(w: Weights) -> [i32; 3] {
+        [w.collapsed_dragons, w.foundation_rank, w.sorting_run]
+    }
+}
+
+impl fmt::Display for Weights {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, w) in WEIGHT_NAMES.iter().zip(<[i32; 3]>::from(*self)) {
+            writeln!(f, "{} {}", name, w)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeightsError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for WeightsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for WeightsError {}
+
+impl FromStr for Weights {
+    type Err = WeightsError;
+
+    fn from_str(s: &str) -> Result<Weights, WeightsError> {
+        let mut weights: [i32; 3] = Weights::default().into();
+        for (i, line) in s.lines().enumerate() {
+            let error = |message: String| WeightsError {
+                line: i + 1,
+                message,
+            };
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut tokens = line.split_whitespace();
+            let (Some(name), Some(value), None) = (tokens.next(), tokens.next(), tokens.next())
+            else {
+                return Err(error(format!(
+                    "expected a name and a value, got '{}'",
+                    line
+                )));
+            };
+            let index = WEIGHT_NAMES
+                .iter()
+                .position(|&n| n == name)
+                .ok_or_else(|| error(format!("unknown weight '{}'", name)))?;
+            weights[index] = value
+                .parse()
+                .map_err(|_| error(format!("invalid value '{}'", value)))?;
+        }
+        Ok(weights.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let weights = Weights::from([250, -3, 7]);
+        assert_eq!(weights.to_string().parse(), Ok(weights));
+        assert_eq!(
+            "# tuned\nsorting_run 2  # runs\n\n".parse(),
+            Ok(Weights {
+                sorting_run: 2,
+                ..Weights::default()
+            })
+        );
+        assert_eq!(
+            "sorting_run 2\nfreecells 4".parse::<Weights>(),
+            Err(WeightsError {
+                line: 2,
+                message: "unknown weight 'freecells'".to_owned()
+            })
+        );
+        assert!("sorting_run".parse::<Weights>().is_err());
+        assert!("sorting_run x".parse::<Weights>().is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use std::env;
+use std::fs;
+use std::io::{self, BufRead};
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+use solitaire_core::ai::{AiResult, AiState, BestFirst, Budget, SOLVERS, Solver, solver};
+use solitaire_core::board::Board;
+use solitaire_core::deal::Deal;
+use solitaire_core::json::Value;
+use solitaire_core::notation::Solution;
+
+const USAGE: &str = "\
+usage: solitaire-solve [OPTIONS] [DEAL...]
+
+Solves the given deals, each a deal code or a seed. Deals are read from stdin, one per line,
+if none are given.
+
+options:
+  -a, --algorithm NAMES search algorithms, separated by commas: astar (default), iddfs,
+                        beam, montecarlo or all; more than one prints a comparison
+  -n, --nodes N         give up after expanding N nodes (default 100000)
+  -t, --time SECONDS    give up after SECONDS of search
+  -m, --memory MB       give up when the search would take more than MB of memory
+                        (default 512)
+  -w, --weights FILE    score states with the weights in FILE, see solitaire-tune
+      --table MB        memory for the table of visited states (default 64)
+  -j, --threads N       search with N threads, 0 for one per core (default 1), which
+                        share the budget and may find a different solution
+      --json            print one JSON object per deal
+  -h, --help            print this message";
+
+struct Options {
+    solvers: Vec<Box<dyn Solver>>,
+    budget: Budget,
+    json: bool,
+    deals: Vec<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        solvers: vec![Box::new(BestFirst)],
+        budget: Budget::nodes(100_000),
+        json: false,
+        deals: Vec::new(),
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "-a" | "--algorithm" => {
+                let names = value()?;
+                let names: Vec<&str> = match names.as_str() {
+                    "all" => SOLVERS.to_vec(),
+                    names => names.split(',').collect(),
+                };
+                options.solvers = names
+                    .into_iter()
+                    .map(|name| solver(name).ok_or(format!("unknown algorithm '{}'", name)))
+                    .collect::<Result<_, _>>()?;
+            }
+            "-n" | "--nodes" => {
+                let n = value()?;
+                options.budget.nodes = n
+                    .parse()
+                    .map_err(|_| format!("invalid node count '{}'", n))?;
+            }
+            "-t" | "--time" => {
+                let t = value()?;
+                let time = t
+                    .parse()
+                    .ok()
+                    .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                    .ok_or(format!("invalid time '{}'", t))?;
+                options.budget.time = Some(time);
+            }
+            "-m" | "--memory" => {
+                let mb = value()?;
+                let mb: usize = mb.parse().map_err(|_| format!("invalid memory '{}'", mb))?;
+                options.budget.memory = Some(mb << 20);
+            }
+            "-w" | "--weights" => {
+                let path = value()?;
+                let text = fs::read_to_string(path)
+                    .map_err(|e| format!("could not read {}: {}", path, e))?;
+                options.budget.weights = text.parse().map_err(|e| format!("{}: {}", path, e))?;
+            }
+            "--table" => {
+                let mb = value()?;
+                let mb: usize = mb
+                    .parse()
+                    .map_err(|_| format!("invalid table size '{}'", mb))?;
+                options.budget.table_bytes = mb << 20;
+            }
+            "-j" | "--threads" => {
+                let n = value()?;
+                let n: usize = n
+                    .parse()
+                    .map_err(|_| format!("invalid thread count '{}'", n))?;
+                options.budget.threads = if n == 0 {
+                    thread::available_parallelism().map_or(1, |n| n.get())
+                } else {
+                    n
+                };
+            }
+            "--json" => options.json = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            a if a.starts_with('-') => return Err(format!("unknown option '{}'", a)),
+            deal => options.deals.push(deal.to_owned()),
+        }
+    }
+    Ok(options)
+}
+
+/// Totals of one solver over all deals.
+#[derive(Default)]
+struct Summary {
+    winnable: usize,
+    lost: usize,
+    unknown: usize,
+    nodes: usize,
+    elapsed: Duration,
+}
+
+fn solve(deal: &Deal, solver: &dyn Solver, options: &Options, summary: &mut Summary) {
+    let board = Board::new(deal);
+    let state = AiState::new(board.stacks().to_vec());
+    let report = solver.solve(&state, &options.budget);
+    let stats = report.stats;
+
+    let (verdict, moves) = match report.result {
+        AiResult::Winable(path) => {
+            summary.winnable += 1;
+            ("winnable", Some(board.player_moves(&path)))
+        }
+        AiResult::Lost => {
+            summary.lost += 1;
+            ("lost", None)
+        }
+        AiResult::Unknown => {
+            summary.unknown += 1;
+            ("unknown", None)
+        }
+    };
+    summary.nodes += stats.expanded;
+    summary.elapsed += stats.elapsed;
+
+    if options.json {
+        let moves = moves.map(|moves| moves.iter().map(|m| m.to_string()).collect::<Vec<_>>());
+        let value = Value::object([
+            ("deal", Value::from(deal.to_string())),
+            ("algorithm", Value::from(solver.name())),
+            ("verdict", Value::from(verdict)),
+            ("moves", Value::from(moves)),
+            ("nodes", Value::from(stats.expanded)),
+            ("generated", Value::from(stats.generated)),
+            ("max_open", Value::from(stats.max_open)),
+            ("duplicates", Value::from(stats.duplicates)),
+            (
+                "best_score",
+                Value::from(stats.best_score.map(|s| s as f64)),
+            ),
+            ("seconds", Value::from(stats.elapsed.as_secs_f64())),
+            ("table_hit_rate", Value::from(stats.table.hit_rate())),
+        ]);
+        println!("{}", value);
+    } else {
+        println!("# {}: {}", solver.name(), stats);
+        if stats.table.probes > 0 {
+            println!(
+                "# {} of {} table probes hit ({:.1}%), {} states pushed out",
+                stats.table.hits,
+                stats.table.probes,
+                100.0 * stats.table.hit_rate(),
+                stats.table.replaced
+            );
+        }
+        match moves {
+            Some(moves) => {
+                println!("# {} in {} moves", verdict, moves.len());
+                print!(
+                    "{}",
+                    Solution {
+                        deal: deal.clone(),
+                        moves
+                    }
+                );
+            }
+            None => {
+                println!("# {}", verdict);
+                println!("deal {}", deal);
+            }
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let deals: Box<dyn Iterator<Item = String>> = if options.deals.is_empty() {
+        Box::new(
+            io::stdin()
+                .lock()
+                .lines()
+                .map(|line| line.expect("Could not read stdin"))
+                .map(|line| line.split('#').next().unwrap().trim().to_owned())
+                .filter(|line| !line.is_empty()),
+        )
+    } else {
+        Box::new(options.deals.clone().into_iter())
+    };
+
+    let mut failed = false;
+    let mut summaries: Vec<Summary> = options.solvers.iter().map(|_| Summary::default()).collect();
+    for text in deals {
+        match text.parse::<Deal>() {
+            Ok(deal) => {
+                for (solver, summary) in options.solvers.iter().zip(&mut summaries) {
+                    solve(&deal, solver.as_ref(), &options, summary);
+                }
+            }
+            Err(e) => {
+                eprintln!("{}: {}", text, e);
+                failed = true;
+            }
+        }
+    }
+    if options.solvers.len() > 1 && !options.json {
+        println!("# algorithm   winnable  lost  unknown      nodes   seconds");
+        for (solver, s) in options.solvers.iter().zip(&summaries) {
+            println!(
+                "# {:<10} {:>9} {:>5} {:>8} {:>10} {:>9.2}",
+                solver.name(),
+                s.winnable,
+                s.lost,
+                s.unknown,
+                s.nodes,
+                s.elapsed.as_secs_f64()
+            );
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,176 @@
+use std::env;
+use std::fs;
+use std::process;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use solitaire_core::ai::{AiResult, AiState, Budget, WEIGHT_NAMES, Weights};
+use solitaire_core::board::Board;
+use solitaire_core::deal::Deal;
+
+const USAGE: &str = "\
+usage: solitaire-tune [OPTIONS]
+
+Tunes the weights the solver scores states with by coordinate descent, to expand as few nodes
+as possible on a corpus of seeded deals. A deal the solver gives up on counts with the whole
+node budget. The best weights so far are saved after every improvement.
+
+options:
+  -d, --deals N         size of the corpus (default 20)
+  -s, --seed SEED       seed of the first deal, the others follow (default 1)
+  -n, --nodes N         node budget per deal (default 100000)
+  -r, --rounds N        stop after N rounds over all weights (default 10)
+  -w, --weights FILE    start from the weights in FILE instead of the defaults
+  -o, --output FILE     where to save the best weights (default weights.txt)
+  -j, --threads N       deals solved at once (default one per core)
+  -h, --help            print this message";
+
+/// The smallest change of a weight, relative to its value, before the search stops.
+const MIN_STEP: f64 = 1.0 / 64.0;
+
+struct Options {
+    deals: u64,
+    seed: u64,
+    nodes: usize,
+    rounds: usize,
+    weights: Weights,
+    output: String,
+    threads: usize,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        deals: 20,
+        seed: 1,
+        nodes: 100_000,
+        rounds: 10,
+        weights: Weights::default(),
+        output: "weights.txt".to_owned(),
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        let mut number = |what| {
+            let n = value()?;
+            n.parse::<usize>()
+                .map_err(|_| format!("invalid {} '{}'", what, n))
+        };
+        match arg.as_str() {
+            "-d" | "--deals" => {
+                options.deals = match number("deal count")? {
+                    0 => return Err("the corpus needs at least one deal".to_owned()),
+                    n => n as u64,
+                }
+            }
+            "-s" | "--seed" => options.seed = number("seed")? as u64,
+            "-n" | "--nodes" => options.nodes = number("node count")?,
+            "-r" | "--rounds" => options.rounds = number("round count")?,
+            "-j" | "--threads" => options.threads = number("thread count")?.max(1),
+            "-w" | "--weights" => {
+                let path = value()?;
+                let text = fs::read_to_string(path)
+                    .map_err(|e| format!("could not read {}: {}", path, e))?;
+                options.weights = text.parse().map_err(|e| format!("{}: {}", path, e))?;
+            }
+            "-o" | "--output" => options.output = value()?.clone(),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            a => return Err(format!("unknown option '{}'", a)),
+        }
+    }
+    Ok(options)
+}
+
+/// Average nodes the solver expands on the corpus with the weights.
+fn cost(weights: Weights, corpus: &[AiState], options: &Options) -> f64 {
+    let budget = Budget {
+        weights,
+        ..Budget::nodes(options.nodes)
+    };
+    let next = AtomicUsize::new(0);
+    let total = Mutex::new(0);
+    thread::scope(|scope| {
+        for _ in 0..options.threads {
+            scope.spawn(|| {
+                while let Some(state) = corpus.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let nodes = match state.astar_with_stats(&budget) {
+                        (AiResult::Unknown, _) => options.nodes,
+                        (_, stats) => stats.expanded,
+                    };
+                    *total.lock().unwrap() += nodes;
+                }
+            });
+        }
+    });
+    total.into_inner().unwrap() as f64 / corpus.len() as f64
+}
+
+fn save(weights: Weights, cost: f64, options: &Options) {
+    let text = format!(
+        "# solitaire-tune: {:.1} nodes on average over {} deals from seed {}, {} nodes each\n{}",
+        cost, options.deals, options.seed, options.nodes, weights
+    );
+    if let Err(e) = fs::write(&options.output, text) {
+        eprintln!("could not write {}: {}", options.output, e);
+        process::exit(1);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let corpus: Vec<AiState> = (0..options.deals)
+        .map(|i| {
+            let board = Board::new(&Deal::from_seed(options.seed.wrapping_add(i)));
+            AiState::new(board.stacks().to_vec())
+        })
+        .collect();
+
+    let mut best = options.weights;
+    let mut best_cost = cost(best, &corpus, &options);
+    println!("start: {:.1} nodes with {:?}", best_cost, best);
+    save(best, best_cost, &options);
+
+    let mut step = 0.5;
+    for round in 1..=options.rounds {
+        let mut improved = false;
+        for i in 0..WEIGHT_NAMES.len() {
+            let w: [i32; 3] = best.into();
+            let delta = ((w[i].abs() as f64 * step).round() as i32).max(1);
+            for value in [w[i] + delta, w[i] - delta] {
+                let mut candidate = w;
+                candidate[i] = value;
+                let candidate = Weights::from(candidate);
+                let c = cost(candidate, &corpus, &options);
+                println!("  {} {}: {:.1} nodes", WEIGHT_NAMES[i], value, c);
+                if c < best_cost {
+                    best = candidate;
+                    best_cost = c;
+                    improved = true;
+                    save(best, best_cost, &options);
+                    break;
+                }
+            }
+        }
+        println!("round {}: {:.1} nodes with {:?}", round, best_cost, best);
+        if !improved {
+            if step <= MIN_STEP {
+                break;
+            }
+            step /= 2.0;
+        }
+    }
+    println!("saved to {}", options.output);
+}
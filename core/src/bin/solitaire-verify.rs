@@ -0,0 +1,53 @@
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Instant;
+
+use solitaire_core::replay::verify;
+
+const USAGE: &str = "\
+usage: solitaire-verify FILE...
+
+Verifies that replays are wins: plays every move again from the deal, checks that there is a
+seed and that it makes the deal, that every move is legal and that all cards end on the
+foundations. Prints the move count and the length of every verified game, and why the others
+were rejected. Exits with status 1 if a replay was rejected.
+
+options:
+  -h, --help            print this message";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    if args.is_empty() {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+
+    let mut rejected = 0;
+    for path in &args {
+        let start = Instant::now();
+        let result = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| verify(&text).map_err(|e| e.to_string()));
+        match result {
+            Ok(replay) => println!(
+                "{}: won in {} moves, {:.3}s (verified in {:.3}s)",
+                path,
+                replay.moves.len(),
+                replay.duration.as_secs_f64(),
+                start.elapsed().as_secs_f64()
+            ),
+            Err(e) => {
+                println!("{}: rejected: {}", path, e);
+                rejected += 1;
+            }
+        }
+    }
+    if rejected > 0 {
+        process::exit(1);
+    }
+}
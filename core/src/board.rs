@@ -0,0 +1,108 @@
+use std::ops::Range;
+
+use crate::deal::Deal;
+use crate::rules::{self, Move};
+use crate::types::*;
+
+// stack layout shared by the board, the AI and `Game::export`
+pub const SORTING: Range<usize> = 0..8;
+pub const DRAGON: Range<usize> = 8..11;
+pub const FLOWER: usize = 11;
+pub const TARGET: Range<usize> = 12..15;
+pub const N_STACKS: usize = 15;
+
+/// The game position without any presentation: the 15 stacks in the order given by the
+/// constants above.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Board {
+    stacks: Vec<Stack>,
+}
+
+impl Board {
+    pub fn empty() -> Board {
+        let stacks = (0..N_STACKS).map(|i| Stack::new(Board::role(i))).collect();
+        Board { stacks }
+    }
+
+    /// Deals the cards and performs the automatic moves the game would make right after.
+    pub fn new(deal: &Deal) -> Board {
+        let mut board = Board::empty();
+        for (i, column) in deal.columns(SORTING.len()).into_iter().enumerate() {
+            board.stacks[SORTING.start + i] = column;
+        }
+        board.settle();
+        board
+    }
+
+    pub fn from_stacks(stacks: Vec<Stack>) -> Board {
+        assert_eq!(stacks.len(), N_STACKS);
+        Board { stacks }
+    }
+
+    pub fn role(idx: usize) -> StackRole {
+        match idx {
+            i if SORTING.contains(&i) => StackRole::Sorting,
+            i if DRAGON.contains(&i) => StackRole::Dragon,
+            FLOWER => StackRole::Flower,
+            i if TARGET.contains(&i) => StackRole::Target,
+            _ => panic!("Invalid stack index {}", idx),
+        }
+    }
+
+    pub fn stacks(&self) -> &[Stack] {
+        &self.stacks
+    }
+
+    pub fn into_stacks(self) -> Vec<Stack> {
+        self.stacks
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        rules::calc_possible_moves(self.stacks.iter())
+    }
+
+    pub fn is_legal(&self, m: &Move) -> bool {
+        self.legal_moves().contains(m)
+    }
+
+    /// Applies a player move followed by the automatic moves it triggers. Returns the number
+    /// of automatic moves.
+    pub fn apply(&mut self, m: &Move) -> Result<usize, Move> {
+        if !self.is_legal(m) {
+            return Err(*m);
+        }
+        rules::apply_move(&mut self.stacks, m);
+        Ok(self.settle())
+    }
+
+    /// Applies a move without checking it or triggering automatic moves.
+    pub fn apply_unchecked(&mut self, m: &Move) {
+        rules::apply_move(&mut self.stacks, m);
+    }
+
+    pub fn settle(&mut self) -> usize {
+        let mut n = 0;
+        while let Some((t, s)) = rules::get_automove(self.stacks.iter()) {
+            rules::apply_move(&mut self.stacks, &Move::Cards(t, s, 1));
+            n += 1;
+        }
+        n
+    }
+
+    pub fn is_won(&self) -> bool {
+        rules::check_victory(self.stacks.iter())
+    }
+
+    /// Drops the automatic moves from a path that includes them, e.g. a path found by the AI.
+    pub fn player_moves(&self, path: &[Move]) -> Vec<Move> {
+        let mut board = self.clone();
+        let mut moves = Vec::new();
+        for m in path {
+            if rules::get_automove(board.stacks.iter()).is_none() {
+                moves.push(*m);
+            }
+            board.apply_unchecked(m);
+        }
+        moves
+    }
+}
@@ -0,0 +1,234 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::types::*;
+
+pub const DECK_SIZE: usize = 40;
+
+// one symbol per distinct card: numbers by color, then the dragons, then the flower
+const CODE_ALPHABET: &[u8; 31] = b"0123456789ABCDEFGHIJKLMNOPQRSTU";
+const COLORS: [Color; 3] = [Color::Red, Color::Green, Color::White];
+
+/// The order in which the 40 cards are dealt onto the sorting stacks. Card `i` lands on
+/// stack `i % 8`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Deal {
+    cards: Vec<Suite>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DealError {
+    Length(usize),
+    Symbol(char),
+    NotADeck,
+    Seed(String),
+}
+
+impl Deal {
+    pub fn new(cards: Vec<Suite>) -> Result<Deal, DealError> {
+        if cards.len() != DECK_SIZE {
+            return Err(DealError::Length(cards.len()));
+        }
+        if cards.contains(&Suite::FaceDown) {
+            return Err(DealError::NotADeck);
+        }
+        let mut sorted: Vec<_> = cards.iter().map(|&c| card_index(c)).collect();
+        let mut expected: Vec<_> = full_deck().into_iter().map(card_index).collect();
+        sorted.sort();
+        expected.sort();
+        if sorted != expected {
+            return Err(DealError::NotADeck);
+        }
+        Ok(Deal { cards })
+    }
+
+    /// Deals are generated with our own PRNG and shuffle so that a seed produces the same
+    /// deal across versions of the `rand` crate.
+    pub fn from_seed(seed: u64) -> Deal {
+        let mut cards = full_deck();
+        let mut rng = SplitMix64(seed);
+        for i in (1..cards.len()).rev() {
+            let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+            cards.swap(i, j);
+        }
+        Deal { cards }
+    }
+
+    pub fn random_seed() -> u64 {
+        rand::random()
+    }
+
+    pub fn from_code(code: &str) -> Result<Deal, DealError> {
+        let code = code.trim();
+        let cards = code
+            .chars()
+            .map(|ch| {
+                // `as u8` would turn other characters into letters of the alphabet
+                if !ch.is_ascii() {
+                    return Err(DealError::Symbol(ch));
+                }
+                let upper = ch.to_ascii_uppercase() as u8;
+                CODE_ALPHABET
+                    .iter()
+                    .position(|&a| a == upper)
+                    .map(index_card)
+                    .ok_or(DealError::Symbol(ch))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Deal::new(cards)
+    }
+
+    pub fn code(&self) -> String {
+        self.cards
+            .iter()
+            .map(|&c| CODE_ALPHABET[card_index(c)] as char)
+            .collect()
+    }
+
+    pub fn cards(&self) -> &[Suite] {
+        &self.cards
+    }
+
+    pub fn columns(&self, n: usize) -> Vec<Stack> {
+        let mut columns = vec![Stack::new(StackRole::Sorting); n];
+        for (i, &card) in self.cards.iter().enumerate() {
+            columns[i % n].push_card(card);
+        }
+        columns
+    }
+}
+
+/// Accepts either a deal code or a decimal seed. A deal code always contains letters, because
+/// the ten digits cannot encode all 31 distinct cards.
+impl FromStr for Deal {
+    type Err = DealError;
+
+    fn from_str(s: &str) -> Result<Deal, DealError> {
+        let s = s.trim();
+        if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) {
+            s.parse()
+                .map(Deal::from_seed)
+                .map_err(|_| DealError::Seed(s.to_owned()))
+        } else {
+            Deal::from_code(s)
+        }
+    }
+}
+
+impl fmt::Display for Deal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+impl fmt::Display for DealError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DealError::Length(n) => write!(f, "a deal has {} cards, not {}", DECK_SIZE, n),
+            DealError::Symbol(c) => write!(f, "invalid symbol '{}' in deal code", c),
+            DealError::NotADeck => write!(f, "deal is not a complete deck"),
+            DealError::Seed(s) => write!(f, "invalid seed '{}'", s),
+        }
+    }
+}
+
+impl Error for DealError {}
+
+pub fn full_deck() -> Vec<Suite> {
+    let mut cards = Vec::with_capacity(DECK_SIZE);
+
+    for _ in 0..4 {
+        cards.push(Suite::Dragon(Color::Red));
+        cards.push(Suite::Dragon(Color::Green));
+        cards.push(Suite::Dragon(Color::White));
+    }
+
+    for i in 1..10 {
+        cards.push(Suite::Number(i, Color::Red));
+        cards.push(Suite::Number(i, Color::Green));
+        cards.push(Suite::Number(i, Color::White));
+    }
+
+    cards.push(Suite::Flower);
+    cards
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::Red => 0,
+        Color::Green => 1,
+        Color::White => 2,
+    }
+}
+
+fn card_index(card: Suite) -> usize {
+    match card {
+        Suite::Number(n, c) => color_index(c) * 9 + n as usize - 1,
+        Suite::Dragon(c) => 27 + color_index(c),
+        Suite::Flower => 30,
+        Suite::FaceDown => panic!("Face down cards are not part of a deal"),
+    }
+}
+
+fn index_card(idx: usize) -> Suite {
+    match idx {
+        0..=26 => Suite::Number((idx % 9) as u8 + 1, COLORS[idx / 9]),
+        27..=29 => Suite::Dragon(COLORS[idx - 27]),
+        _ => Suite::Flower,
+    }
+}
+
+/// The generator behind seeded deals. Small and stable, for anything else that has to be
+/// reproducible from a seed.
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64(seed)
+    }
+
+    /// A number below `n`, which must not be zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_roundtrip() {
+        for seed in 0..100 {
+            let deal = Deal::from_seed(seed);
+            assert_eq!(Deal::from_code(&deal.code()), Ok(deal.clone()));
+            assert_eq!(deal.code().parse(), Ok(deal));
+        }
+        assert_eq!("42".parse(), Ok(Deal::from_seed(42)));
+    }
+
+    #[test]
+    fn invalid_codes() {
+        let code = Deal::from_seed(7).code();
+        assert_eq!(Deal::from_code(&code[1..]), Err(DealError::Length(39)));
+        assert_eq!(
+            Deal::from_code(&format!("V{}", &code[1..])),
+            Err(DealError::Symbol('V'))
+        );
+        // U+0130 truncated to a byte would be '0'
+        assert_eq!(
+            Deal::from_code(&format!("\u{130}{}", &code[1..])),
+            Err(DealError::Symbol('\u{130}'))
+        );
+        assert_eq!(Deal::from_code(&"0".repeat(40)), Err(DealError::NotADeck));
+    }
+}
@@ -0,0 +1,12 @@
+//! The rules, the AI and the board model of the game, without any graphics or audio.
+
+pub mod ai;
+pub mod board;
+pub mod deal;
+pub mod json;
+pub mod notation;
+pub mod replay;
+pub mod rules;
+pub mod stats;
+pub mod types;
+pub mod variations;
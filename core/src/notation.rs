@@ -0,0 +1,268 @@
+//! Textual notation for moves and solution files.
+//!
+//! Cards are written as rank and color, `5r`, dragons as `Dg`, the flower as `F` and a face
+//! down card as `X`.
+//!
+//! Stacks are named `1`-`8` for the sorting stacks, `a`-`c` for the dragon cells, `f` for the
+//! flower slot and `x`-`z` for the foundations. A card move is written as source followed by
+//! target, plus `:n` if it moves more than one card: `37`, `a4`, `2x`, `15:3`. Collapsing the
+//! dragons is written as `D` followed by the color: `Dr`, `Dg`, `Dw`.
+//!
+//! A solution file holds a `deal` line with the deal code (or seed) followed by the moves,
+//! separated by whitespace. Text after `#` is a comment. Automatic moves are implied and not
+//! written down.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::board::Board;
+use crate::deal::{Deal, DealError};
+use crate::rules::{self, Move};
+use crate::types::*;
+
+const STACK_NAMES: &[u8; 15] = b"12345678abcfxyz";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotationError {
+    Syntax(String),
+    Illegal(String),
+    AtMove(usize, Box<NotationError>),
+    MissingDeal,
+    Deal(DealError),
+}
+
+pub fn stack_name(idx: usize) -> char {
+    STACK_NAMES[idx] as char
+}
+
+pub fn stack_index(name: char) -> Option<usize> {
+    STACK_NAMES.iter().position(|&n| n as char == name)
+}
+
+pub fn color_name(color: Color) -> char {
+    match color {
+        Color::Red => 'r',
+        Color::Green => 'g',
+        Color::White => 'w',
+    }
+}
+
+pub fn parse_color(name: &str) -> Option<Color> {
+    match name {
+        "r" | "red" => Some(Color::Red),
+        "g" | "green" => Some(Color::Green),
+        "w" | "white" => Some(Color::White),
+        _ => None,
+    }
+}
+
+pub fn card_name(card: Suite) -> String {
+    match card {
+        Suite::FaceDown => "X".to_owned(),
+        Suite::Flower => "F".to_owned(),
+        Suite::Dragon(c) => format!("D{}", color_name(c)),
+        Suite::Number(n, c) => format!("{}{}", n, color_name(c)),
+    }
+}
+
+pub fn parse_card(token: &str) -> Option<Suite> {
+    match token {
+        "X" => Some(Suite::FaceDown),
+        "F" => Some(Suite::Flower),
+        _ => {
+            let (head, color) = token.split_at(token.len().checked_sub(1)?);
+            let color = parse_color(color)?;
+            match head {
+                "D" => Some(Suite::Dragon(color)),
+                _ => match head.parse() {
+                    Ok(n @ 1..=9) => Some(Suite::Number(n, color)),
+                    _ => None,
+                },
+            }
+        }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Move::Button(color, _, _) => write!(f, "D{}", color_name(color)),
+            Move::Cards(t, s, 1) => write!(f, "{}{}", stack_name(s), stack_name(t)),
+            Move::Cards(t, s, n) => write!(f, "{}{}:{}", stack_name(s), stack_name(t), n),
+        }
+    }
+}
+
+/// Parses a single move. The stacks are needed to find the dragon cells involved in a collapse,
+/// so a collapse is only accepted if it is possible in the given position.
+pub fn parse_move(token: &str, stacks: &[Stack]) -> Result<Move, NotationError> {
+    let syntax = || NotationError::Syntax(token.to_owned());
+
+    if let Some(color) = token.strip_prefix('D') {
+        let color = parse_color(color).ok_or_else(syntax)?;
+        let (t, s) = rules::check_button(color, stacks.iter())
+            .ok_or_else(|| NotationError::Illegal(token.to_owned()))?;
+        return Ok(Move::Button(color, t, s));
+    }
+
+    let (names, n) = match token.split_once(':') {
+        Some((names, n)) => (names, n.parse().map_err(|_| syntax())?),
+        None => (token, 1),
+    };
+    let mut chars = names.chars();
+    let (s, t) = match (chars.next(), chars.next(), chars.next()) {
+        (Some(s), Some(t), None) => (s, t),
+        _ => return Err(syntax()),
+    };
+    let s = stack_index(s).ok_or_else(syntax)?;
+    let t = stack_index(t).ok_or_else(syntax)?;
+    if n == 0 {
+        return Err(syntax());
+    }
+    Ok(Move::Cards(t, s, n))
+}
+
+/// Parses a whitespace separated list of moves and plays them on the board, which must end up
+/// in the position after the last move.
+pub fn play_moves(text: &str, board: &mut Board) -> Result<Vec<Move>, NotationError> {
+    let mut moves = Vec::new();
+    for (index, token) in tokens(text).enumerate() {
+        let m = parse_move(token, board.stacks())
+            .and_then(|m| {
+                board
+                    .apply(&m)
+                    .map_err(|_| NotationError::Illegal(token.to_owned()))?;
+                Ok(m)
+            })
+            .map_err(|e| NotationError::AtMove(index, Box::new(e)))?;
+        moves.push(m);
+    }
+    Ok(moves)
+}
+
+fn tokens(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap())
+        .flat_map(str::split_whitespace)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    pub deal: Deal,
+    pub moves: Vec<Move>,
+}
+
+impl Solution {
+    /// The board after playing all moves.
+    pub fn board(&self) -> Board {
+        let mut board = Board::new(&self.deal);
+        for m in &self.moves {
+            board.apply(m).expect("Solution contains an illegal move");
+        }
+        board
+    }
+}
+
+impl FromStr for Solution {
+    type Err = NotationError;
+
+    fn from_str(text: &str) -> Result<Solution, NotationError> {
+        let mut lines = text.lines();
+        let deal = loop {
+            let line = lines.next().ok_or(NotationError::MissingDeal)?;
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            match line.strip_prefix("deal ") {
+                Some(code) => break code.parse::<Deal>().map_err(NotationError::Deal)?,
+                None => return Err(NotationError::MissingDeal),
+            }
+        };
+
+        let rest = lines.collect::<Vec<_>>().join("\n");
+        let mut board = Board::new(&deal);
+        let moves = play_moves(&rest, &mut board)?;
+        Ok(Solution { deal, moves })
+    }
+}
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "deal {}", self.deal)?;
+        for line in self.moves.chunks(10) {
+            let line: Vec<_> = line.iter().map(|m| m.to_string()).collect();
+            writeln!(f, "{}", line.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotationError::Syntax(token) => write!(f, "invalid move '{}'", token),
+            NotationError::Illegal(token) => write!(f, "move '{}' is not legal", token),
+            NotationError::AtMove(index, e) => write!(f, "move {}: {}", index + 1, e),
+            NotationError::MissingDeal => write!(f, "missing deal line"),
+            NotationError::Deal(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for NotationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deal::full_deck;
+
+    #[test]
+    fn move_roundtrip() {
+        let board = Board::new(&Deal::from_seed(1));
+        for m in board.legal_moves() {
+            assert_eq!(parse_move(&m.to_string(), board.stacks()), Ok(m));
+        }
+        for token in ["", "1", "123", "19", "12:0", "12:x", "Dx", "Dr"] {
+            assert!(parse_move(token, board.stacks()).is_err(), "{}", token);
+        }
+    }
+
+    #[test]
+    fn card_names() {
+        for card in full_deck().into_iter().chain([Suite::FaceDown]) {
+            assert_eq!(parse_card(&card_name(card)), Some(card));
+        }
+        for token in ["", "D", "0r", "10r", "5", "Fr"] {
+            assert_eq!(parse_card(token), None, "{}", token);
+        }
+    }
+
+    #[test]
+    fn solution_file() {
+        let deal = Deal::from_seed(3);
+        let mut board = Board::new(&deal);
+        let mut moves = Vec::new();
+        for _ in 0..20 {
+            let m = match board.legal_moves().first() {
+                Some(&m) => m,
+                None => break,
+            };
+            board.apply(&m).unwrap();
+            moves.push(m);
+        }
+        let solution = Solution { deal, moves };
+
+        let text = format!("# comment\n\n{}", solution);
+        assert_eq!(text.parse(), Ok(solution.clone()));
+        assert_eq!(solution.board(), board);
+
+        let illegal = format!("deal {}\n{} 1a 1a 1a 1a", solution.deal, solution.moves[0]);
+        match illegal.parse::<Solution>() {
+            Err(NotationError::AtMove(_, e)) => assert_eq!(*e, NotationError::Illegal("1a".into())),
+            r => panic!("unexpected result {:?}", r),
+        }
+        assert_eq!("37".parse::<Solution>(), Err(NotationError::MissingDeal));
+    }
+}
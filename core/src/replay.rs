@@ -0,0 +1,381 @@
+//! Recorded games: the deal, every player move and when it was made.
+//!
+//! A replay file has an optional `seed` line for deals that came from a seed, a `deal` line
+//! with the deal code, one line per move with the seconds since the start of the game and the
+//! move in the notation of `notation`, and a last line with the outcome and the length of the
+//! game. Text after `#` is a comment.
+//!
+//! ```text
+//! seed 12345
+//! deal 4fKa7...
+//! 1.250 37
+//! 2.031 Dr
+//! won 93.214
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::board::Board;
+use crate::deal::Deal;
+use crate::notation::parse_move;
+use crate::rules::{self, Move};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Won,
+    GaveUp,
+    /// The game was left without giving up, or is still running.
+    Unfinished,
+}
+
+const OUTCOMES: [(Outcome, &str); 3] = [
+    (Outcome::Won, "won"),
+    (Outcome::GaveUp, "gave-up"),
+    (Outcome::Unfinished, "unfinished"),
+];
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (_, name) = OUTCOMES.iter().find(|(o, _)| o == self).unwrap();
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Outcome {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Outcome, ()> {
+        OUTCOMES
+            .iter()
+            .find(|(_, name)| *name == s)
+            .map(|&(o, _)| o)
+            .ok_or(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replay {
+    pub seed: Option<u64>,
+    pub deal: Deal,
+    /// Player moves with the time since the start of the game, automatic moves are implied.
+    pub moves: Vec<(Duration, Move)>,
+    pub outcome: Outcome,
+    pub duration: Duration,
+}
+
+impl Replay {
+    pub fn new(seed: Option<u64>, deal: Deal) -> Replay {
+        Replay {
+            seed,
+            deal,
+            moves: Vec::new(),
+            outcome: Outcome::Unfinished,
+            duration: Duration::ZERO,
+        }
+    }
+
+    /// The board after the first `n` moves.
+    pub fn board(&self, n: usize) -> Board {
+        let mut board = Board::new(&self.deal);
+        for (_, m) in &self.moves[..n] {
+            board.apply(m).expect("Replay contains an illegal move");
+        }
+        board
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ReplayError {}
+
+pub(crate) fn parse_seconds(token: &str) -> Option<Duration> {
+    token
+        .parse::<f64>()
+        .ok()
+        .and_then(|s| Duration::try_from_secs_f64(s).ok())
+}
+
+/// Checks that every move is legal in the position before it, so a replay that parses can be
+/// played back.
+impl FromStr for Replay {
+    type Err = ReplayError;
+
+    fn from_str(text: &str) -> Result<Replay, ReplayError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.split('#').next().unwrap().trim()))
+            .filter(|(_, line)| !line.is_empty());
+        let error = |line, message: String| ReplayError { line, message };
+        let end = text.lines().count();
+
+        let (mut i, mut line) = lines
+            .next()
+            .ok_or_else(|| error(end, "missing deal line".to_owned()))?;
+        let mut seed = None;
+        if let Some(s) = line.strip_prefix("seed ") {
+            let s = s.trim();
+            seed = Some(
+                s.parse()
+                    .map_err(|_| error(i, format!("invalid seed '{}'", s)))?,
+            );
+            (i, line) = lines
+                .next()
+                .ok_or_else(|| error(end, "missing deal line".to_owned()))?;
+        }
+        let deal = line
+            .strip_prefix("deal ")
+            .ok_or_else(|| error(i, "missing deal line".to_owned()))?
+            .parse::<Deal>()
+            .map_err(|e| error(i, e.to_string()))?;
+
+        let mut replay = Replay::new(seed, deal);
+        let mut board = Board::new(&replay.deal);
+        for (i, line) in lines.by_ref() {
+            let (first, second) = line
+                .split_once(char::is_whitespace)
+                .map(|(a, b)| (a, b.trim()))
+                .ok_or_else(|| error(i, format!("expected a time and a move, got '{}'", line)))?;
+            if let Ok(outcome) = first.parse::<Outcome>() {
+                replay.outcome = outcome;
+                replay.duration = parse_seconds(second)
+                    .ok_or_else(|| error(i, format!("invalid time '{}'", second)))?;
+                return match lines.next() {
+                    Some((i, _)) => Err(error(i, "text after the outcome".to_owned())),
+                    None => Ok(replay),
+                };
+            }
+            let time = parse_seconds(first)
+                .ok_or_else(|| error(i, format!("invalid time '{}'", first)))?;
+            let m = parse_move(second, board.stacks()).map_err(|e| error(i, e.to_string()))?;
+            board
+                .apply(&m)
+                .map_err(|_| error(i, format!("move '{}' is not legal", second)))?;
+            replay.moves.push((time, m));
+        }
+        Err(error(end, "missing outcome line".to_owned()))
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(seed) = self.seed {
+            writeln!(f, "seed {}", seed)?;
+        }
+        writeln!(f, "deal {}", self.deal)?;
+        for (time, m) in &self.moves {
+            writeln!(f, "{:.3} {}", time.as_secs_f64(), m)?;
+        }
+        writeln!(f, "{} {:.3}", self.outcome, self.duration.as_secs_f64())
+    }
+}
+
+/// Why a replay is not a valid win.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    Parse(ReplayError),
+    /// The replay has no seed, so its deal could have been picked by hand.
+    Unseeded,
+    /// The seed does not make the deal of the replay.
+    SeedMismatch(u64),
+    /// A move, counted from 0, is not legal in the position before it.
+    Illegal(usize, Move),
+    /// A move, counted from 0, was made before the one before it, or the game ended before its
+    /// last move for the number of moves.
+    TimeTravel(usize),
+    /// The replay claims something else than a win.
+    NotClaimed(Outcome),
+    /// The cards are not all on the foundations after this many moves.
+    NotWon(usize),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::Parse(e) => write!(f, "{}", e),
+            VerifyError::Unseeded => {
+                write!(f, "the replay has no seed, so its deal cannot be checked")
+            }
+            VerifyError::SeedMismatch(seed) => {
+                write!(f, "seed {} does not make the deal of the replay", seed)
+            }
+            VerifyError::Illegal(i, m) => write!(f, "move {} ({}) is not legal", i + 1, m),
+            VerifyError::TimeTravel(i) => {
+                write!(f, "move {} is earlier than the move before", i + 1)
+            }
+            VerifyError::NotClaimed(outcome) => {
+                write!(f, "the replay claims no win but {}", outcome)
+            }
+            VerifyError::NotWon(n) => write!(f, "the game is not won after {} moves", n),
+        }
+    }
+}
+
+impl Error for VerifyError {}
+
+impl Replay {
+    /// Plays the replay again from its deal and checks that it is a win: there is a seed and it
+    /// makes the deal, every move is legal, the times go forward and all cards end on the foundations.
+    pub fn verify(&self) -> Result<(), VerifyError> {
+        let seed = self.seed.ok_or(VerifyError::Unseeded)?;
+        if Deal::from_seed(seed) != self.deal {
+            return Err(VerifyError::SeedMismatch(seed));
+        }
+        let mut board = Board::new(&self.deal);
+        let mut last = Duration::ZERO;
+        for (i, &(time, m)) in self.moves.iter().enumerate() {
+            if time < last {
+                return Err(VerifyError::TimeTravel(i));
+            }
+            board.apply(&m).map_err(|m| VerifyError::Illegal(i, m))?;
+            last = time;
+        }
+        if self.duration < last {
+            return Err(VerifyError::TimeTravel(self.moves.len()));
+        }
+        if self.outcome != Outcome::Won {
+            return Err(VerifyError::NotClaimed(self.outcome));
+        }
+        if !rules::check_victory(board.stacks().iter()) {
+            return Err(VerifyError::NotWon(self.moves.len()));
+        }
+        Ok(())
+    }
+}
+
+/// Parses a replay and verifies that it is a win.
+pub fn verify(text: &str) -> Result<Replay, VerifyError> {
+    let replay: Replay = text.parse().map_err(VerifyError::Parse)?;
+    replay.verify()?;
+    Ok(replay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{AiResult, AiState, Budget};
+
+    #[test]
+    fn roundtrip() {
+        let deal = Deal::from_seed(3);
+        let mut replay = Replay::new(Some(3), deal.clone());
+        let mut board = Board::new(&deal);
+        for i in 0..5 {
+            let m = board.legal_moves()[0];
+            board.apply(&m).unwrap();
+            replay.moves.push((Duration::from_millis(1500 * i), m));
+        }
+        replay.outcome = Outcome::GaveUp;
+        replay.duration = Duration::from_millis(9250);
+        assert_eq!(replay.board(5), board);
+
+        let text = replay.to_string();
+        assert_eq!(text.parse(), Ok(replay.clone()));
+
+        replay.seed = None;
+        let text = format!("# no seed\n{}", replay);
+        assert_eq!(text.parse(), Ok(replay));
+    }
+
+    #[test]
+    fn errors() {
+        let deal = Deal::from_seed(3);
+        let parse = |body: &str| format!("deal {}\n{}", deal, body).parse::<Replay>();
+        let error = |line, message: &str| {
+            Err(ReplayError {
+                line,
+                message: message.to_owned(),
+            })
+        };
+        assert_eq!(
+            parse("won 1.0"),
+            Ok(Replay {
+                outcome: Outcome::Won,
+                duration: Duration::from_secs(1),
+                ..Replay::new(None, deal.clone())
+            })
+        );
+        assert_eq!(parse(""), error(1, "missing outcome line"));
+        assert_eq!(parse("1.0 99\nwon 2"), error(2, "invalid move '99'"));
+        assert_eq!(parse("x 12\nwon 2"), error(2, "invalid time 'x'"));
+        assert_eq!(parse("-1 12\nwon 2"), error(2, "invalid time '-1'"));
+        assert_eq!(parse("won 2\n1.0 12"), error(3, "text after the outcome"));
+        assert_eq!(
+            parse("37"),
+            error(2, "expected a time and a move, got '37'")
+        );
+        assert_eq!("won 1".parse::<Replay>(), error(1, "missing deal line"));
+        assert_eq!(
+            format!("seed x\ndeal {}\nwon 1", deal).parse::<Replay>(),
+            error(1, "invalid seed 'x'")
+        );
+
+        // the dragon cells are empty after the deal
+        assert_eq!(parse("1 a1\nwon 2"), error(2, "move 'a1' is not legal"));
+    }
+
+    #[test]
+    fn verification() {
+        let deal = Deal::from_seed(5);
+        let ai = AiState::new(Board::new(&deal).stacks().to_vec());
+        let AiResult::Winable(path) = ai.astar(&Budget::nodes(20_000)) else {
+            panic!("seed 5 is not solved");
+        };
+        let moves = Board::new(&deal).player_moves(&path);
+        let mut won = Replay::new(Some(5), deal);
+        won.moves = (0..moves.len() as u64)
+            .map(Duration::from_secs)
+            .zip(moves)
+            .collect();
+        won.outcome = Outcome::Won;
+        won.duration = Duration::from_secs(won.moves.len() as u64);
+        assert_eq!(verify(&won.to_string()), Ok(won.clone()));
+
+        let mut r = won.clone();
+        r.seed = Some(6);
+        assert_eq!(r.verify(), Err(VerifyError::SeedMismatch(6)));
+
+        let mut r = won.clone();
+        r.seed = None;
+        assert_eq!(r.verify(), Err(VerifyError::Unseeded));
+
+        let mut r = won.clone();
+        r.moves.pop();
+        assert_eq!(r.verify(), Err(VerifyError::NotWon(r.moves.len())));
+
+        let mut r = won.clone();
+        let bogus = Move::Cards(0, 1, 40);
+        r.moves[0].1 = bogus;
+        assert_eq!(r.verify(), Err(VerifyError::Illegal(0, bogus)));
+
+        let mut r = won.clone();
+        r.moves[3].0 = Duration::ZERO;
+        assert_eq!(r.verify(), Err(VerifyError::TimeTravel(3)));
+
+        let mut r = won.clone();
+        r.duration = Duration::ZERO;
+        assert_eq!(r.verify(), Err(VerifyError::TimeTravel(r.moves.len())));
+
+        let mut r = won.clone();
+        r.outcome = Outcome::GaveUp;
+        assert_eq!(r.verify(), Err(VerifyError::NotClaimed(Outcome::GaveUp)));
+
+        assert!(matches!(
+            verify("deal 1\n1 a1\nwon 2"),
+            Err(VerifyError::Parse(_))
+        ));
+    }
+}
@@ -76,6 +76,11 @@ pub fn is_valid_move(target: &Stack, base_card: Suite, n_cards: usize) -> bool {
     }
 }
 
+#[allow(
+    clippy::needless_borrow,
+    clippy::needless_borrowed_reference,
+    clippy::question_mark
+)]
 pub fn check_button<'a, I: Iterator<Item = &'a Stack> + Clone>(
     color: Color,
     stacks: I,
@@ -167,12 +172,30 @@ pub fn get_automove<'a, I: Iterator<Item = &'a Stack> + Clone>(
     None
 }
 
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum Move {
     Button(Color, usize, [usize; 4]),
     Cards(usize, usize, usize),
 }
 
+pub fn apply_move(stacks: &mut [Stack], m: &Move) {
+    match *m {
+        Move::Button(_, t, s) => {
+            for &i in &s {
+                stacks[i].pop_card();
+            }
+            for _ in 0..4 {
+                stacks[t].push_card(Suite::FaceDown);
+            }
+        }
+        Move::Cards(t, s, n) => {
+            let i = stacks[s].len() - n;
+            let tmp = stacks[s].split(i);
+            stacks[t].extend(tmp);
+        }
+    }
+}
+
 pub fn calc_possible_moves<'a, I: Iterator<Item = &'a Stack> + Clone>(stacks: I) -> Vec<Move> {
     let mut moves = Vec::new();
 
@@ -250,6 +273,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::useless_vec)]
     fn valid_sequences() {
         use self::Color::*;
         use self::Suite::*;
@@ -0,0 +1,346 @@
+//! Statistics over all games played: totals, streaks, records and the history of every game.
+//!
+//! The statistics file starts with the version of the format, followed by the totals and one
+//! `game` line per game, oldest first, with the time it started in milliseconds since the epoch,
+//! the seed or `-`, the outcome, the length in seconds, the number of moves and the deal code.
+//! Text after `#` is a comment.
+//!
+//! ```text
+//! version 1
+//! played 12
+//! won 5
+//! gave-up 3
+//! streak 2
+//! best-streak 4
+//! fastest 93.214
+//! fewest-moves 87
+//! game 1700000000000 12345 won 93.214 87 4fKa7...
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::deal::{Deal, DealError};
+use crate::replay::{Outcome, parse_seconds};
+
+/// The version of the file format written by `Display`.
+pub const VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameRecord {
+    /// Milliseconds since the epoch, this also names the replay of the game.
+    pub started: u64,
+    pub seed: Option<u64>,
+    pub deal: Deal,
+    pub outcome: Outcome,
+    pub duration: Duration,
+    pub moves: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub played: u32,
+    pub won: u32,
+    pub gave_up: u32,
+    /// Games won in a row, up to the last game.
+    pub streak: u32,
+    pub best_streak: u32,
+    pub fastest: Option<Duration>,
+    pub fewest_moves: Option<usize>,
+    /// Every game recorded, oldest first. Migrated totals have no history.
+    pub history: Vec<GameRecord>,
+}
+
+impl Stats {
+    /// The statistics of a plain win counter, from before there were statistics.
+    pub fn from_wins(wins: u32) -> Stats {
+        Stats {
+            played: wins,
+            won: wins,
+            ..Stats::default()
+        }
+    }
+
+    /// Counts a finished game in the totals and the streaks. A game given up ends the streak,
+    /// unfinished games are left out.
+    pub fn count(&mut self, outcome: Outcome, duration: Option<Duration>, moves: Option<usize>) {
+        match outcome {
+            Outcome::Won => {
+                self.played += 1;
+                self.won += 1;
+                self.streak += 1;
+                self.best_streak = self.best_streak.max(self.streak);
+                if let Some(d) = duration {
+                    self.fastest = Some(self.fastest.map_or(d, |f| f.min(d)));
+                }
+                if let Some(n) = moves {
+                    self.fewest_moves = Some(self.fewest_moves.map_or(n, |f| f.min(n)));
+                }
+            }
+            Outcome::GaveUp => {
+                self.played += 1;
+                self.gave_up += 1;
+                self.streak = 0;
+            }
+            Outcome::Unfinished => {}
+        }
+    }
+
+    /// Counts a game and adds it to the history.
+    pub fn record(&mut self, game: GameRecord) {
+        self.count(game.outcome, Some(game.duration), Some(game.moves));
+        self.history.push(game);
+    }
+
+    /// Won games per game played, 0 before the first game.
+    pub fn win_rate(&self) -> f64 {
+        match self.played {
+            0 => 0.0,
+            n => self.won as f64 / n as f64,
+        }
+    }
+
+    pub fn wins(&self) -> impl Iterator<Item = &GameRecord> {
+        self.history.iter().filter(|g| g.outcome == Outcome::Won)
+    }
+}
+
+/// Counts of values in ranges of equal width, from the smallest value to the largest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    pub min: f64,
+    pub width: f64,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    /// Sorts the values into `bins` ranges, or a single one if all values are the same.
+    pub fn new(values: &[f64], bins: usize) -> Histogram {
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if values.is_empty() || min == max {
+            return Histogram {
+                min: if values.is_empty() { 0.0 } else { min },
+                width: 1.0,
+                counts: vec![values.len()],
+            };
+        }
+        let width = (max - min) / bins as f64;
+        let mut counts = vec![0; bins];
+        for v in values {
+            let bin = ((v - min) / width) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+        Histogram { min, width, counts }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatsError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for StatsError {}
+
+impl FromStr for GameRecord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<GameRecord, String> {
+        let fields: Vec<_> = s.split_whitespace().collect();
+        let &[started, seed, outcome, duration, moves, deal] = fields.as_slice() else {
+            return Err(format!("expected 6 fields, got {}", fields.len()));
+        };
+        Ok(GameRecord {
+            started: started
+                .parse()
+                .map_err(|_| format!("invalid start time '{}'", started))?,
+            seed: match seed {
+                "-" => None,
+                s => Some(s.parse().map_err(|_| format!("invalid seed '{}'", s))?),
+            },
+            outcome: outcome
+                .parse()
+                .map_err(|_| format!("invalid outcome '{}'", outcome))?,
+            duration: parse_seconds(duration)
+                .ok_or_else(|| format!("invalid time '{}'", duration))?,
+            moves: moves
+                .parse()
+                .map_err(|_| format!("invalid move count '{}'", moves))?,
+            deal: deal.parse().map_err(|e: DealError| e.to_string())?,
+        })
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.seed {
+            Some(seed) => write!(f, "{} {}", self.started, seed)?,
+            None => write!(f, "{} -", self.started)?,
+        }
+        write!(
+            f,
+            " {} {:.3} {} {}",
+            self.outcome,
+            self.duration.as_secs_f64(),
+            self.moves,
+            self.deal
+        )
+    }
+}
+
+/// Reads files of this and older versions, newer ones are rejected instead of losing what
+/// this version does not know about.
+impl FromStr for Stats {
+    type Err = StatsError;
+
+    fn from_str(text: &str) -> Result<Stats, StatsError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.split('#').next().unwrap().trim()))
+            .filter(|(_, line)| !line.is_empty());
+        let error = |line, message: String| StatsError { line, message };
+
+        let (i, line) = lines
+            .next()
+            .ok_or_else(|| error(text.lines().count(), "missing version line".to_owned()))?;
+        let version = line
+            .strip_prefix("version ")
+            .ok_or_else(|| error(i, "missing version line".to_owned()))?
+            .trim();
+        match version.parse::<u32>() {
+            Ok(v) if v > VERSION => {
+                return Err(error(i, format!("version {} is newer than {}", v, VERSION)));
+            }
+            Ok(_) => {}
+            Err(_) => return Err(error(i, format!("invalid version '{}'", version))),
+        }
+
+        let mut stats = Stats::default();
+        for (i, line) in lines {
+            let (key, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let value = value.trim();
+            let count = || {
+                value
+                    .parse::<u32>()
+                    .map_err(|_| error(i, format!("invalid count '{}'", value)))
+            };
+            match key {
+                "played" => stats.played = count()?,
+                "won" => stats.won = count()?,
+                "gave-up" => stats.gave_up = count()?,
+                "streak" => stats.streak = count()?,
+                "best-streak" => stats.best_streak = count()?,
+                "fewest-moves" => stats.fewest_moves = Some(count()? as usize),
+                "fastest" => {
+                    stats.fastest = Some(
+                        parse_seconds(value)
+                            .ok_or_else(|| error(i, format!("invalid time '{}'", value)))?,
+                    )
+                }
+                "game" => stats.history.push(value.parse().map_err(|e| error(i, e))?),
+                _ => return Err(error(i, format!("unknown entry '{}'", key))),
+            }
+        }
+        Ok(stats)
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "version {}", VERSION)?;
+        writeln!(f, "played {}", self.played)?;
+        writeln!(f, "won {}", self.won)?;
+        writeln!(f, "gave-up {}", self.gave_up)?;
+        writeln!(f, "streak {}", self.streak)?;
+        writeln!(f, "best-streak {}", self.best_streak)?;
+        if let Some(fastest) = self.fastest {
+            writeln!(f, "fastest {:.3}", fastest.as_secs_f64())?;
+        }
+        if let Some(moves) = self.fewest_moves {
+            writeln!(f, "fewest-moves {}", moves)?;
+        }
+        for game in &self.history {
+            writeln!(f, "game {}", game)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(outcome: Outcome, secs: u64, moves: usize) -> GameRecord {
+        GameRecord {
+            started: 1_700_000_000_000 + secs,
+            seed: Some(secs),
+            deal: Deal::from_seed(secs),
+            outcome,
+            duration: Duration::from_secs(secs),
+            moves,
+        }
+    }
+
+    #[test]
+    fn record() {
+        let mut stats = Stats::from_wins(3);
+        stats.record(game(Outcome::Won, 90, 80));
+        stats.record(game(Outcome::Won, 120, 70));
+        stats.record(game(Outcome::GaveUp, 30, 10));
+        stats.record(game(Outcome::Won, 100, 75));
+        stats.record(game(Outcome::Unfinished, 5, 2));
+
+        assert_eq!((stats.played, stats.won, stats.gave_up), (7, 6, 1));
+        assert_eq!((stats.streak, stats.best_streak), (1, 2));
+        assert_eq!(stats.fastest, Some(Duration::from_secs(90)));
+        assert_eq!(stats.fewest_moves, Some(70));
+        assert_eq!(stats.history.len(), 5);
+
+        // a win without a record, e.g. of a restored game
+        stats.count(Outcome::Won, None, None);
+        assert_eq!((stats.played, stats.won, stats.streak), (8, 7, 2));
+        assert_eq!(stats.history.len(), 5);
+    }
+
+    #[test]
+    fn histogram() {
+        let h = Histogram::new(&[10.0, 12.0, 19.0, 30.0, 50.0], 4);
+        assert_eq!((h.min, h.width), (10.0, 10.0));
+        assert_eq!(h.counts, vec![3, 0, 1, 1]);
+        assert_eq!(Histogram::new(&[7.0, 7.0], 4).counts, vec![2]);
+        assert_eq!(Histogram::new(&[], 4).counts, vec![0]);
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut stats = Stats::default();
+        assert_eq!(stats.to_string().parse(), Ok(stats.clone()));
+        stats.record(game(Outcome::Won, 93, 87));
+        let mut unseeded = game(Outcome::GaveUp, 12, 3);
+        unseeded.seed = None;
+        stats.record(unseeded);
+        assert_eq!(stats.to_string().parse(), Ok(stats));
+    }
+
+    #[test]
+    fn errors() {
+        let line = |text: &str| text.parse::<Stats>().unwrap_err().line;
+        assert_eq!(line(""), 0);
+        assert_eq!(line("played 3\n"), 1);
+        assert_eq!(line("version 2\n"), 1);
+        assert_eq!(line("version 1\nwon x\n"), 2);
+        assert_eq!(line("# stats\nversion 1\n\nlost 3\n"), 4);
+        assert_eq!(line("version 1\ngame 1 - won 2.0 3\n"), 2);
+        assert_eq!(line("version 1\ngame 1 - drawn 2.0 3 x\n"), 2);
+    }
+}
@@ -1,9 +1,5 @@
 use std::slice;
 
-use ggez::graphics;
-
-pub use crate::resources::Sounds;
-
 pub const CARD_WIDTH: f32 = 123.0;
 pub const CARD_HEIGHT: f32 = 233.0;
 
@@ -13,12 +9,25 @@ pub const BUTTON_RADIUS_SQUARED: f32 = BUTTON_RADIUS * BUTTON_RADIUS;
 pub type Point2 = nalgebra::Point2<f32>;
 pub type Vector2 = nalgebra::Vector2<f32>;
 
+/// Handle of an entity. The generation changes whenever the world is cleared, so handles from
+/// before never refer to the entities created afterwards.
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
-pub struct Entity(usize);
+pub struct Entity {
+    id: usize,
+    generation: u32,
+}
 
 impl Entity {
-    pub fn new(id: usize) -> Entity {
-        Entity(id)
+    pub fn new(id: usize, generation: u32) -> Entity {
+        Entity { id, generation }
+    }
+
+    pub fn id(self) -> usize {
+        self.id
+    }
+
+    pub fn generation(self) -> u32 {
+        self.generation
     }
 }
 
@@ -29,21 +38,13 @@ pub enum Color {
     White,
 }
 
-impl Color {
-    pub fn to_font_color(&self) -> graphics::Color {
-        match *self {
-            Color::Red => graphics::Color::new(0.7, 0.2, 0.1, 1.0),
-            Color::Green => graphics::Color::new(0.1, 0.4, 0.3, 1.0),
-            Color::White => graphics::Color::new(0.1, 0.1, 0.1, 1.0),
-        }
-    }
-    pub fn to_icon_color(&self) -> graphics::Color {
-        match *self {
-            Color::Red => graphics::Color::new(1.0, 1.0, 1.0, 1.0),
-            Color::Green => graphics::Color::new(0.1, 0.4, 0.3, 1.0),
-            Color::White => graphics::Color::new(1.0, 1.0, 1.0, 1.0),
-        }
-    }
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Sounds {
+    None,
+    Pickup,
+    Place,
+    Sweep,
+    Deal,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
@@ -53,7 +54,7 @@ pub enum ButtonState {
     Down,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Button {
     pub color: Color,
     pub state: ButtonState,
@@ -106,10 +107,15 @@ impl Stack {
         self.cards.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
     pub fn iter(&self) -> slice::Iter<'_, Suite> {
         self.cards.iter()
     }
 
+    #[allow(clippy::map_clone)]
     pub fn top(&self) -> Option<Suite> {
         self.cards.last().map(|s| *s)
     }
@@ -150,6 +156,7 @@ impl Stack {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct Animation {
     pub start_delay: f32,
     pub time_left: f32,
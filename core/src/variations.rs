@@ -0,0 +1,340 @@
+//! A tree of alternative lines of play from a deal, for analysing positions.
+//!
+//! A tree is saved as a `deal` line with the deal code followed by the moves in the notation of
+//! `notation`, like in PGN: a variation in parentheses is an alternative to the move before it,
+//! and a comment in braces annotates the move before it, or the deal if it comes first.
+//!
+//! ```text
+//! deal 4fKa7...
+//! {a hard one} 37 46 {forced} (48 Dr {wins quickly}) 15
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::board::Board;
+use crate::deal::Deal;
+use crate::notation::{NotationError, parse_move};
+use crate::rules::Move;
+
+/// A node of the tree, the position after the moves on the way from the root.
+pub type NodeId = usize;
+
+/// The node of the dealt position.
+pub const ROOT: NodeId = 0;
+
+#[derive(Clone, Debug)]
+struct Node {
+    parent: NodeId,
+    last_move: Option<Move>,
+    children: Vec<NodeId>,
+    comment: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Variations {
+    deal: Deal,
+    nodes: Vec<Node>,
+}
+
+impl Variations {
+    pub fn new(deal: Deal) -> Variations {
+        let root = Node {
+            parent: ROOT,
+            last_move: None,
+            children: Vec::new(),
+            comment: String::new(),
+        };
+        Variations {
+            deal,
+            nodes: vec![root],
+        }
+    }
+
+    pub fn deal(&self) -> &Deal {
+        &self.deal
+    }
+
+    pub fn parent(&self, node: NodeId) -> Option<NodeId> {
+        (node != ROOT).then(|| self.nodes[node].parent)
+    }
+
+    /// The continuations of a node, the main line first.
+    pub fn children(&self, node: NodeId) -> &[NodeId] {
+        &self.nodes[node].children
+    }
+
+    /// The move that leads to a node, `None` for the root.
+    pub fn last_move(&self, node: NodeId) -> Option<Move> {
+        self.nodes[node].last_move
+    }
+
+    pub fn comment(&self, node: NodeId) -> &str {
+        &self.nodes[node].comment
+    }
+
+    /// Replaces the comment of a node. Braces would end the comment in the file, so they are
+    /// dropped.
+    pub fn annotate(&mut self, node: NodeId, comment: &str) {
+        self.nodes[node].comment = comment.replace(['{', '}'], "").trim().to_owned();
+    }
+
+    /// The moves from the root to a node.
+    pub fn line(&self, mut node: NodeId) -> Vec<Move> {
+        let mut moves = Vec::new();
+        while let Some(m) = self.last_move(node) {
+            moves.push(m);
+            node = self.nodes[node].parent;
+        }
+        moves.reverse();
+        moves
+    }
+
+    /// The position of a node, after the automatic moves.
+    pub fn board(&self, node: NodeId) -> Board {
+        let mut board = Board::new(&self.deal);
+        for m in self.line(node) {
+            board.apply(&m).expect("Variations contain an illegal move");
+        }
+        board
+    }
+
+    /// The node after a move from `node`, a new variation unless the move was played there
+    /// before. Fails like `Board::apply` if the move is not legal.
+    pub fn play(&mut self, node: NodeId, m: Move) -> Result<NodeId, Move> {
+        if let Some(&child) = self
+            .children(node)
+            .iter()
+            .find(|&&c| self.last_move(c) == Some(m))
+        {
+            return Ok(child);
+        }
+        self.board(node).apply(&m)?;
+        self.nodes.push(Node {
+            parent: node,
+            last_move: Some(m),
+            children: Vec::new(),
+            comment: String::new(),
+        });
+        let child = self.nodes.len() - 1;
+        self.nodes[node].children.push(child);
+        Ok(child)
+    }
+
+    /// Appends the move and comment of a node.
+    fn push_move(&self, node: NodeId, tokens: &mut Vec<String>) {
+        tokens.push(self.last_move(node).unwrap().to_string());
+        if !self.comment(node).is_empty() {
+            tokens.push(format!("{{{}}}", self.comment(node)));
+        }
+    }
+
+    /// Appends the main line after a node, with the variations of each move after it.
+    fn push_line(&self, mut node: NodeId, tokens: &mut Vec<String>) {
+        while let Some((&main, others)) = self.children(node).split_first() {
+            self.push_move(main, tokens);
+            for &other in others {
+                let start = tokens.len();
+                self.push_move(other, tokens);
+                tokens[start].insert(0, '(');
+                self.push_line(other, tokens);
+                tokens.last_mut().unwrap().push(')');
+            }
+            node = main;
+        }
+    }
+}
+
+/// Trees are equal if they hold the same lines, variations and comments in the same order,
+/// whatever order their nodes were added in.
+impl PartialEq for Variations {
+    fn eq(&self, other: &Variations) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
+
+impl Eq for Variations {}
+
+enum Token<'a> {
+    Move(&'a str),
+    Comment(&'a str),
+    Open,
+    Close,
+}
+
+fn tokens(text: &str) -> Result<Vec<Token<'_>>, NotationError> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = match c {
+            '(' => {
+                tokens.push(Token::Open);
+                1
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                1
+            }
+            '{' => {
+                let end = rest
+                    .find('}')
+                    .ok_or_else(|| NotationError::Syntax(rest.to_owned()))?;
+                tokens.push(Token::Comment(rest[1..end].trim()));
+                end + 1
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || "(){}".contains(c))
+                    .unwrap_or(rest.len());
+                tokens.push(Token::Move(&rest[..end]));
+                end
+            }
+        };
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+impl FromStr for Variations {
+    type Err = NotationError;
+
+    fn from_str(text: &str) -> Result<Variations, NotationError> {
+        let text = text.trim_start();
+        let (first, rest) = text.split_once('\n').unwrap_or((text, ""));
+        let code = first
+            .trim()
+            .strip_prefix("deal ")
+            .ok_or(NotationError::MissingDeal)?;
+        let deal = code.parse::<Deal>().map_err(NotationError::Deal)?;
+
+        let mut tree = Variations::new(deal);
+        let mut node = ROOT;
+        // where to continue after the variations that are open
+        let mut open = Vec::new();
+        let mut index = 0;
+        for token in tokens(rest)? {
+            match token {
+                Token::Move(token) => {
+                    let board = tree.board(node);
+                    node = parse_move(token, board.stacks())
+                        .and_then(|m| {
+                            tree.play(node, m)
+                                .map_err(|_| NotationError::Illegal(token.to_owned()))
+                        })
+                        .map_err(|e| NotationError::AtMove(index, Box::new(e)))?;
+                    index += 1;
+                }
+                Token::Comment(comment) => tree.annotate(node, comment),
+                Token::Open => {
+                    // the variation replaces the move before it
+                    let parent = tree
+                        .parent(node)
+                        .ok_or_else(|| NotationError::Syntax("(".to_owned()))?;
+                    open.push(node);
+                    node = parent;
+                }
+                Token::Close => {
+                    node = open
+                        .pop()
+                        .ok_or_else(|| NotationError::Syntax(")".to_owned()))?;
+                }
+            }
+        }
+        if !open.is_empty() {
+            return Err(NotationError::Syntax("(".to_owned()));
+        }
+        Ok(tree)
+    }
+}
+
+impl fmt::Display for Variations {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "deal {}", self.deal)?;
+        let mut tokens = Vec::new();
+        if !self.comment(ROOT).is_empty() {
+            tokens.push(format!("{{{}}}", self.comment(ROOT)));
+        }
+        self.push_line(ROOT, &mut tokens);
+        writeln!(f, "{}", tokens.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn branches() {
+        let mut tree = Variations::new(Deal::from_seed(1));
+        let moves = tree.board(ROOT).legal_moves();
+        let a = tree.play(ROOT, moves[0]).unwrap();
+        let b = tree.play(ROOT, moves[1]).unwrap();
+        assert_eq!(tree.play(ROOT, moves[0]), Ok(a));
+        assert_eq!(tree.children(ROOT), &[a, b]);
+        assert_eq!(tree.parent(b), Some(ROOT));
+        assert_eq!(tree.parent(ROOT), None);
+
+        let next = tree.board(b).legal_moves()[0];
+        let c = tree.play(b, next).unwrap();
+        assert_eq!(tree.line(c), vec![moves[1], next]);
+        let mut board = Board::new(&Deal::from_seed(1));
+        board.apply(&moves[1]).unwrap();
+        board.apply(&next).unwrap();
+        assert_eq!(tree.board(c), board);
+
+        let illegal = Move::Cards(0, 1, 40);
+        assert_eq!(tree.play(c, illegal), Err(illegal));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut tree = Variations::new(Deal::from_seed(7));
+        tree.annotate(ROOT, "a {hard} one");
+        let mut node = ROOT;
+        for i in 0..6 {
+            let moves = tree.board(node).legal_moves();
+            if i % 2 == 0 && moves.len() > 1 {
+                let other = tree.play(node, moves[1]).unwrap();
+                tree.annotate(other, "the other way");
+                let then = tree.board(other).legal_moves()[0];
+                tree.play(other, then).unwrap();
+            }
+            node = tree.play(node, moves[0]).unwrap();
+        }
+        tree.annotate(node, "and so on");
+
+        let text = tree.to_string();
+        assert!(text.starts_with("deal "));
+        assert!(text.contains("{a hard one}"));
+        assert_eq!(text.parse(), Ok(tree));
+    }
+
+    #[test]
+    fn errors() {
+        let deal = Deal::from_seed(1);
+        let first = Board::new(&deal).legal_moves()[0];
+        let parse = |body: &str| format!("deal {}\n{}", deal, body).parse::<Variations>();
+        assert!(parse(&format!("{} ({})", first, first)).is_ok());
+        assert_eq!(
+            parse("(").unwrap_err(),
+            NotationError::Syntax("(".to_owned())
+        );
+        assert_eq!(
+            parse(&format!("{} (", first)).unwrap_err(),
+            NotationError::Syntax("(".to_owned())
+        );
+        assert_eq!(
+            parse(&format!("{} )", first)).unwrap_err(),
+            NotationError::Syntax(")".to_owned())
+        );
+        assert!(matches!(parse("{open"), Err(NotationError::Syntax(_))));
+        assert!(matches!(
+            parse(&format!("{} 99", first)),
+            Err(NotationError::AtMove(1, _))
+        ));
+        assert_eq!(
+            "37 46".parse::<Variations>(),
+            Err(NotationError::MissingDeal)
+        );
+    }
+}
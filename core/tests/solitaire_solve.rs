@@ -0,0 +1,33 @@
+//! Runs the `solitaire-solve` binary.
+
+use std::process::{Command, Output};
+
+fn solve(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_solitaire-solve"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn time_limits() {
+    // fits in a Duration but not in a deadline, so there is none
+    let out = solve(&["-t", "1e19", "-n", "1000", "1"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(String::from_utf8_lossy(&out.stdout).contains("deal "));
+
+    for time in ["1e30", "-1", "inf", "NaN", "soon"] {
+        let out = solve(&["-t", time, "1"]);
+        assert_eq!(out.status.code(), Some(2), "{}", time);
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        assert!(
+            stderr.starts_with(&format!("invalid time '{}'", time)),
+            "{}",
+            stderr
+        );
+    }
+}
@@ -0,0 +1,116 @@
+/* Generated from src/lib.rs by tests/header.rs, do not edit. */
+
+#ifndef SOLITAIRE_H
+#define SOLITAIRE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SOL_MOVE_CARDS 0
+
+#define SOL_MOVE_DRAGONS 1
+
+#define SOL_COLOR_RED 0
+
+#define SOL_COLOR_GREEN 1
+
+#define SOL_COLOR_WHITE 2
+
+#define SOL_UNKNOWN 0
+
+#define SOL_WINNABLE 1
+
+#define SOL_LOST 2
+
+#define SOL_ERROR (-1)
+
+typedef struct SolBoard SolBoard;
+
+/**
+ * A move as seen from C. `color` is only used by dragon moves, `from`, `to` and `count` only by
+ * card moves.
+ */
+typedef struct SolMove {
+    uint32_t kind;
+    uint32_t color;
+    uint32_t from;
+    uint32_t to;
+    uint32_t count;
+} SolMove;
+
+/**
+ * Deals the game for a seed, as the game and `solitaire-solve` do.
+ */
+SolBoard *sol_board_from_seed(uint64_t seed);
+
+/**
+ * Returns NULL if the code is not a valid deal code or seed.
+ *
+ * # Safety
+ * `code` must be a valid NUL terminated string.
+ */
+SolBoard *sol_board_from_code(const char *code);
+
+/**
+ * # Safety
+ * `board` must be NULL or a pointer returned by this library that has not been freed yet.
+ */
+void sol_board_free(SolBoard *board);
+
+/**
+ * Writes up to `capacity` legal moves to `moves` and returns the total number of legal moves.
+ *
+ * # Safety
+ * `board` must be a valid board and `moves` must point to `capacity` writable moves, or be
+ * NULL if `capacity` is 0.
+ */
+size_t sol_board_legal_moves(const SolBoard *board, SolMove *moves, size_t capacity);
+
+/**
+ * Applies a move and the automatic moves following it. Returns 0 on success and -1 if the move
+ * is not legal, in which case the board is unchanged.
+ *
+ * # Safety
+ * `board` must be a valid board and `m` must point to a move.
+ */
+int32_t sol_board_apply(SolBoard *board, const SolMove *m);
+
+/**
+ * # Safety
+ * `board` must be a valid board.
+ */
+bool sol_board_is_won(const SolBoard *board);
+
+/**
+ * Searches for a solution from the current position, giving up after `max_nodes` expanded
+ * positions, `max_seconds` seconds (0 for no time limit) or about 512 MB of memory. Returns
+ * `SOL_WINNABLE`, `SOL_LOST`, `SOL_UNKNOWN` or `SOL_ERROR` for invalid arguments. For a winnable
+ * position up to `capacity` moves of the solution are written to `moves` and the length of the
+ * solution to `n_moves`.
+ *
+ * # Safety
+ * `board` must be a valid board, `moves` must point to `capacity` writable moves (or be NULL if
+ * `capacity` is 0) and `n_moves` must be NULL or point to a writable `size_t`.
+ */
+int32_t sol_solve(const SolBoard *board, uint64_t max_nodes, double max_seconds, SolMove *moves, size_t capacity, size_t *n_moves);
+
+/**
+ * Formats a move in the notation of `solitaire_core::notation` and writes it as a NUL
+ * terminated string. Returns the length of the notation, excluding the NUL, or -1 if the move
+ * is invalid. The output is truncated if it does not fit into `len` bytes.
+ *
+ * # Safety
+ * `m` must point to a move and `buf` must point to `len` writable bytes.
+ */
+int32_t sol_move_format(const SolMove *m, char *buf, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SOLITAIRE_H */
@@ -0,0 +1,253 @@
+//! C interface to the rules and the solver. The matching header is `include/solitaire.h`.
+//!
+//! Boards are opaque heap objects created by `sol_board_from_seed` or `sol_board_from_code` and
+//! released with `sol_board_free`. Stacks are numbered as in `solitaire_core::board`: 0-7 are the
+//! sorting stacks, 8-10 the dragon cells, 11 the flower slot and 12-14 the foundations.
+
+use std::ffi::{CStr, c_char};
+use std::ptr;
+use std::slice;
+use std::time::Duration;
+
+use solitaire_core::ai::{AiResult, AiState, Budget};
+use solitaire_core::board::{Board, N_STACKS};
+use solitaire_core::deal::Deal;
+use solitaire_core::rules::{self, Move};
+use solitaire_core::types::Color;
+
+pub const SOL_MOVE_CARDS: u32 = 0;
+pub const SOL_MOVE_DRAGONS: u32 = 1;
+
+pub const SOL_COLOR_RED: u32 = 0;
+pub const SOL_COLOR_GREEN: u32 = 1;
+pub const SOL_COLOR_WHITE: u32 = 2;
+
+pub const SOL_UNKNOWN: i32 = 0;
+pub const SOL_WINNABLE: i32 = 1;
+pub const SOL_LOST: i32 = 2;
+pub const SOL_ERROR: i32 = -1;
+
+pub struct SolBoard {
+    board: Board,
+}
+
+/// A move as seen from C. `color` is only used by dragon moves, `from`, `to` and `count` only by
+/// card moves.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolMove {
+    pub kind: u32,
+    pub color: u32,
+    pub from: u32,
+    pub to: u32,
+    pub count: u32,
+}
+
+fn color_to_c(color: Color) -> u32 {
+    match color {
+        Color::Red => SOL_COLOR_RED,
+        Color::Green => SOL_COLOR_GREEN,
+        Color::White => SOL_COLOR_WHITE,
+    }
+}
+
+fn color_from_c(color: u32) -> Option<Color> {
+    match color {
+        SOL_COLOR_RED => Some(Color::Red),
+        SOL_COLOR_GREEN => Some(Color::Green),
+        SOL_COLOR_WHITE => Some(Color::White),
+        _ => None,
+    }
+}
+
+impl From<Move> for SolMove {
+    fn from(m: Move) -> SolMove {
+        match m {
+            Move::Button(color, _, _) => SolMove {
+                kind: SOL_MOVE_DRAGONS,
+                color: color_to_c(color),
+                ..SolMove::default()
+            },
+            Move::Cards(t, s, n) => SolMove {
+                kind: SOL_MOVE_CARDS,
+                from: s as u32,
+                to: t as u32,
+                count: n as u32,
+                ..SolMove::default()
+            },
+        }
+    }
+}
+
+impl SolMove {
+    fn to_move(self, board: &Board) -> Option<Move> {
+        match self.kind {
+            SOL_MOVE_DRAGONS => {
+                let color = color_from_c(self.color)?;
+                let (t, s) = rules::check_button(color, board.stacks().iter())?;
+                Some(Move::Button(color, t, s))
+            }
+            SOL_MOVE_CARDS => {
+                let (s, t, n) = (self.from as usize, self.to as usize, self.count as usize);
+                if s >= N_STACKS || t >= N_STACKS || n == 0 {
+                    return None;
+                }
+                Some(Move::Cards(t, s, n))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn into_ptr(board: Board) -> *mut SolBoard {
+    Box::into_raw(Box::new(SolBoard { board }))
+}
+
+/// Deals the game for a seed, as the game and `solitaire-solve` do.
+#[unsafe(no_mangle)]
+pub extern "C" fn sol_board_from_seed(seed: u64) -> *mut SolBoard {
+    into_ptr(Board::new(&Deal::from_seed(seed)))
+}
+
+/// Returns NULL if the code is not a valid deal code or seed.
+///
+/// # Safety
+/// `code` must be a valid NUL terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sol_board_from_code(code: *const c_char) -> *mut SolBoard {
+    if code.is_null() {
+        return ptr::null_mut();
+    }
+    let code = unsafe { CStr::from_ptr(code) };
+    match code.to_str().ok().and_then(|c| c.parse::<Deal>().ok()) {
+        Some(deal) => into_ptr(Board::new(&deal)),
+        None => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// `board` must be NULL or a pointer returned by this library that has not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sol_board_free(board: *mut SolBoard) {
+    if !board.is_null() {
+        drop(unsafe { Box::from_raw(board) });
+    }
+}
+
+/// Writes up to `capacity` legal moves to `moves` and returns the total number of legal moves.
+///
+/// # Safety
+/// `board` must be a valid board and `moves` must point to `capacity` writable moves, or be
+/// NULL if `capacity` is 0.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sol_board_legal_moves(
+    board: *const SolBoard,
+    moves: *mut SolMove,
+    capacity: usize,
+) -> usize {
+    let board = unsafe { &(*board).board };
+    let legal = board.legal_moves();
+    unsafe { write_moves(&legal, moves, capacity) }
+}
+
+/// Applies a move and the automatic moves following it. Returns 0 on success and -1 if the move
+/// is not legal, in which case the board is unchanged.
+///
+/// # Safety
+/// `board` must be a valid board and `m` must point to a move.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sol_board_apply(board: *mut SolBoard, m: *const SolMove) -> i32 {
+    let board = unsafe { &mut (*board).board };
+    let m = unsafe { *m };
+    match m.to_move(board).map(|m| board.apply(&m)) {
+        Some(Ok(_)) => 0,
+        _ => -1,
+    }
+}
+
+/// # Safety
+/// `board` must be a valid board.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sol_board_is_won(board: *const SolBoard) -> bool {
+    unsafe { (*board).board.is_won() }
+}
+
+/// Searches for a solution from the current position, giving up after `max_nodes` expanded
+/// positions, `max_seconds` seconds (0 for no time limit) or about 512 MB of memory. Returns
+/// `SOL_WINNABLE`, `SOL_LOST`, `SOL_UNKNOWN` or `SOL_ERROR` for invalid arguments. For a winnable
+/// position up to `capacity` moves of the solution are written to `moves` and the length of the
+/// solution to `n_moves`.
+///
+/// # Safety
+/// `board` must be a valid board, `moves` must point to `capacity` writable moves (or be NULL if
+/// `capacity` is 0) and `n_moves` must be NULL or point to a writable `size_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sol_solve(
+    board: *const SolBoard,
+    max_nodes: u64,
+    max_seconds: f64,
+    moves: *mut SolMove,
+    capacity: usize,
+    n_moves: *mut usize,
+) -> i32 {
+    // also rejects negative, NaN and infinite times, and times too long for a Duration
+    let Ok(time) = Duration::try_from_secs_f64(max_seconds) else {
+        return SOL_ERROR;
+    };
+    let board = unsafe { &(*board).board };
+    let budget = Budget {
+        time: (max_seconds > 0.0).then_some(time),
+        ..Budget::nodes(max_nodes as usize)
+    };
+    match AiState::new(board.stacks().to_vec()).astar(&budget) {
+        AiResult::Winable(path) => {
+            let solution = board.player_moves(&path);
+            let n = unsafe { write_moves(&solution, moves, capacity) };
+            if !n_moves.is_null() {
+                unsafe { *n_moves = n };
+            }
+            SOL_WINNABLE
+        }
+        AiResult::Lost => SOL_LOST,
+        AiResult::Unknown => SOL_UNKNOWN,
+    }
+}
+
+/// Formats a move in the notation of `solitaire_core::notation` and writes it as a NUL
+/// terminated string. Returns the length of the notation, excluding the NUL, or -1 if the move
+/// is invalid. The output is truncated if it does not fit into `len` bytes.
+///
+/// # Safety
+/// `m` must point to a move and `buf` must point to `len` writable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sol_move_format(m: *const SolMove, buf: *mut c_char, len: usize) -> i32 {
+    let m = unsafe { *m };
+    let text = match m.kind {
+        // the notation of a collapse does not depend on the dragon cells involved
+        SOL_MOVE_DRAGONS => match color_from_c(m.color) {
+            Some(color) => Move::Button(color, 0, [0; 4]).to_string(),
+            None => return -1,
+        },
+        _ => match m.to_move(&Board::empty()) {
+            Some(m) => m.to_string(),
+            None => return -1,
+        },
+    };
+    if len > 0 {
+        let n = text.len().min(len - 1);
+        let out = unsafe { slice::from_raw_parts_mut(buf as *mut u8, len) };
+        out[..n].copy_from_slice(&text.as_bytes()[..n]);
+        out[n] = 0;
+    }
+    text.len() as i32
+}
+
+unsafe fn write_moves(moves: &[Move], out: *mut SolMove, capacity: usize) -> usize {
+    if capacity > 0 {
+        let out = unsafe { slice::from_raw_parts_mut(out, capacity) };
+        for (o, &m) in out.iter_mut().zip(moves) {
+            *o = m.into();
+        }
+    }
+    moves.len()
+}
@@ -0,0 +1,101 @@
+/* Exercises the C interface. Exits with 0 if all checks pass. */
+
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "solitaire.h"
+
+#define MAX_MOVES 4096
+
+static int failures = 0;
+
+#define CHECK(cond)                                                  \
+    do {                                                             \
+        if (!(cond)) {                                               \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,   \
+                    __LINE__, #cond);                                \
+            failures++;                                              \
+        }                                                            \
+    } while (0)
+
+static void test_moves(void) {
+    SolBoard *board = sol_board_from_seed(1);
+    CHECK(board != NULL);
+    CHECK(!sol_board_is_won(board));
+
+    size_t n = sol_board_legal_moves(board, NULL, 0);
+    CHECK(n > 0);
+
+    SolMove *moves = calloc(n, sizeof(SolMove));
+    CHECK(sol_board_legal_moves(board, moves, n) == n);
+
+    char text[16];
+    int len = sol_move_format(&moves[0], text, sizeof(text));
+    CHECK(len > 0 && (size_t)len == strlen(text));
+
+    CHECK(sol_board_apply(board, &moves[0]) == 0);
+
+    SolMove invalid = {SOL_MOVE_CARDS, 0, 99, 0, 1};
+    CHECK(sol_board_apply(board, &invalid) == -1);
+    CHECK(sol_move_format(&invalid, text, sizeof(text)) == -1);
+
+    SolMove to_foundation = {SOL_MOVE_CARDS, 0, 0, 12, 5};
+    CHECK(sol_board_apply(board, &to_foundation) == -1);
+
+    free(moves);
+    sol_board_free(board);
+}
+
+static void test_codes(void) {
+    CHECK(sol_board_from_code("not a deal") == NULL);
+    CHECK(sol_board_from_code(NULL) == NULL);
+
+    SolBoard *board = sol_board_from_code("9RKIS7TR328GHAST1BFCSUTSEPMR06JLTORQND54");
+    CHECK(board != NULL);
+    sol_board_free(board);
+
+    board = sol_board_from_code("12345");
+    CHECK(board != NULL);
+    sol_board_free(board);
+}
+
+static void test_solve(void) {
+    SolBoard *board = sol_board_from_seed(5);
+    SolMove *moves = calloc(MAX_MOVES, sizeof(SolMove));
+    size_t n = 0;
+
+    CHECK(sol_solve(board, 100000, -1.0, moves, MAX_MOVES, &n) == SOL_ERROR);
+    CHECK(sol_solve(board, 100000, INFINITY, moves, MAX_MOVES, &n) == SOL_ERROR);
+    CHECK(sol_solve(board, 100000, NAN, moves, MAX_MOVES, &n) == SOL_ERROR);
+    CHECK(sol_solve(board, 100000, 1e30, moves, MAX_MOVES, &n) == SOL_ERROR);
+    /* fits in a Duration but not in a deadline, so there is none */
+    CHECK(sol_solve(board, 100000, 1e19, moves, MAX_MOVES, &n) == SOL_WINNABLE);
+
+    int verdict = sol_solve(board, 100000, 0.0, moves, MAX_MOVES, &n);
+    CHECK(verdict == SOL_WINNABLE);
+    if (verdict == SOL_WINNABLE) {
+        CHECK(n > 0 && n <= MAX_MOVES);
+        for (size_t i = 0; i < n; i++) {
+            CHECK(sol_board_apply(board, &moves[i]) == 0);
+        }
+        CHECK(sol_board_is_won(board));
+    }
+
+    free(moves);
+    sol_board_free(board);
+}
+
+int main(void) {
+    test_moves();
+    test_codes();
+    test_solve();
+
+    if (failures > 0) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return 1;
+    }
+    printf("all checks passed\n");
+    return 0;
+}
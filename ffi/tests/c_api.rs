@@ -0,0 +1,30 @@
+//! Builds `tests/c/test_solitaire.c` against the static library and runs it.
+
+#![cfg(target_os = "linux")]
+
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn c_test_program() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // the test binary lives in target/<profile>/deps, the library in target/<profile>
+    let exe = env::current_exe().unwrap();
+    let lib_dir = exe.parent().unwrap().parent().unwrap();
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("test_solitaire");
+
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".to_owned()))
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-I"])
+        .arg(root.join("include"))
+        .arg(root.join("tests/c/test_solitaire.c"))
+        .arg(lib_dir.join("libsolitaire_ffi.a"))
+        .args(["-lm", "-lpthread", "-ldl", "-o"])
+        .arg(&out)
+        .status()
+        .expect("Could not run the C compiler");
+    assert!(status.success());
+
+    let status = Command::new(&out).status().unwrap();
+    assert!(status.success());
+}
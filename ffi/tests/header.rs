@@ -0,0 +1,196 @@
+//! Generates `include/solitaire.h` from the exported items in `src/lib.rs` and checks that the
+//! shipped header is up to date. Run with `SOLITAIRE_BLESS=1` to rewrite the header.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const PREAMBLE: &str = "\
+/* Generated from src/lib.rs by tests/header.rs, do not edit. */
+
+#ifndef SOLITAIRE_H
+#define SOLITAIRE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern \"C\" {
+#endif
+";
+
+const POSTAMBLE: &str = "
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SOLITAIRE_H */
+";
+
+fn c_type(ty: &str) -> String {
+    let ty = ty.trim();
+    if let Some(inner) = ty.strip_prefix("*mut ") {
+        return format!("{} *", c_type(inner));
+    }
+    if let Some(inner) = ty.strip_prefix("*const ") {
+        return format!("const {} *", c_type(inner));
+    }
+    match ty {
+        "u64" => "uint64_t",
+        "u32" => "uint32_t",
+        "i32" => "int32_t",
+        "usize" => "size_t",
+        "f64" => "double",
+        "bool" => "bool",
+        "c_char" => "char",
+        other => other,
+    }
+    .to_owned()
+}
+
+fn doc_comment(doc: &[String]) -> String {
+    if doc.is_empty() {
+        return String::new();
+    }
+    let mut out = String::from("/**\n");
+    for line in doc {
+        if line.is_empty() {
+            out.push_str(" *\n");
+        } else {
+            out.push_str(&format!(" * {}\n", line));
+        }
+    }
+    out.push_str(" */\n");
+    out
+}
+
+fn generate(source: &str) -> String {
+    let mut out = String::from(PREAMBLE);
+    let mut doc = Vec::new();
+    let mut repr_c = false;
+    let mut lines = source.lines();
+
+    while let Some(line) = lines.next() {
+        let line = line.trim();
+        if let Some(d) = line.strip_prefix("///") {
+            doc.push(d.trim().to_owned());
+            continue;
+        }
+        if line == "#[repr(C)]" {
+            repr_c = true;
+            continue;
+        }
+        if line.starts_with("#[") {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("pub const ") {
+            let (name, rest) = rest.split_once(':').unwrap();
+            let value = rest.split_once('=').unwrap().1.trim_end_matches(';').trim();
+            let value = if value.starts_with('-') {
+                format!("({})", value)
+            } else {
+                value.to_owned()
+            };
+            out.push_str(&format!(
+                "\n{}#define {} {}\n",
+                doc_comment(&doc),
+                name,
+                value
+            ));
+        } else if let Some(rest) = line.strip_prefix("pub struct ") {
+            let name = rest.trim_end_matches('{').trim();
+            if repr_c {
+                out.push_str(&format!(
+                    "\n{}typedef struct {} {{\n",
+                    doc_comment(&doc),
+                    name
+                ));
+                for field in lines.by_ref().map(str::trim).take_while(|l| *l != "}") {
+                    let (fname, fty) = field.strip_prefix("pub ").unwrap().split_once(':').unwrap();
+                    out.push_str(&format!(
+                        "    {} {};\n",
+                        c_type(fty.trim_end_matches(',')),
+                        fname
+                    ));
+                }
+                out.push_str(&format!("}} {};\n", name));
+            } else {
+                out.push_str(&format!(
+                    "\n{}typedef struct {} {};\n",
+                    doc_comment(&doc),
+                    name,
+                    name
+                ));
+            }
+        } else if line.contains("extern \"C\" fn ") {
+            let mut signature = line.to_owned();
+            while !signature.ends_with('{') {
+                signature.push_str(lines.next().unwrap().trim());
+            }
+            let signature = signature.split_once("fn ").unwrap().1;
+            let (name, rest) = signature.split_once('(').unwrap();
+            let (params, ret) = rest.rsplit_once(')').unwrap();
+            let ret = ret.trim_end_matches('{').trim();
+            let ret = match ret.strip_prefix("->") {
+                Some(ty) => c_type(ty),
+                None => "void".to_owned(),
+            };
+            let params: Vec<_> = params
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(|p| {
+                    let (pname, pty) = p.split_once(':').unwrap();
+                    let pty = c_type(pty);
+                    if pty.ends_with('*') {
+                        format!("{}{}", pty, pname)
+                    } else {
+                        format!("{} {}", pty, pname)
+                    }
+                })
+                .collect();
+            let params = if params.is_empty() {
+                "void".to_owned()
+            } else {
+                params.join(", ")
+            };
+            let ret = if ret.ends_with('*') {
+                ret
+            } else {
+                format!("{} ", ret)
+            };
+            out.push_str(&format!(
+                "\n{}{}{}({});\n",
+                doc_comment(&doc),
+                ret,
+                name,
+                params
+            ));
+        }
+
+        doc.clear();
+        repr_c = false;
+    }
+
+    out.push_str(POSTAMBLE);
+    out
+}
+
+#[test]
+fn header_is_up_to_date() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let source = fs::read_to_string(root.join("src/lib.rs")).unwrap();
+    let header_path = root.join("include/solitaire.h");
+    let header = generate(&source);
+
+    if env::var_os("SOLITAIRE_BLESS").is_some() {
+        fs::write(&header_path, &header).unwrap();
+    }
+    let shipped = fs::read_to_string(&header_path).unwrap_or_default();
+    assert!(
+        shipped == header,
+        "include/solitaire.h is out of date, run the tests with SOLITAIRE_BLESS=1"
+    );
+}
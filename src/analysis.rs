@@ -0,0 +1,134 @@
+//! The analysis view (F4): every legal move of the position on the table, evaluated by the
+//! solver on a background thread so the game keeps running while it searches. The thread lives
+//! as long as the view, and gives up on a position as soon as the table moves on.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use solitaire_core::ai::{Budget, DEFAULT_TABLE_BYTES, Verdict, Weights, evaluate};
+use solitaire_core::board::Board;
+use solitaire_core::rules::Move;
+
+use crate::cs::{AnalysedMove, MoveTarget};
+use crate::game::Game;
+use crate::types::*;
+
+/// Budget of the search after every single move.
+const MOVE_BUDGET: Budget = Budget {
+    nodes: 100_000,
+    time: Some(Duration::from_secs(2)),
+    memory: Some(256 << 20),
+    table_bytes: DEFAULT_TABLE_BYTES,
+    threads: 1,
+    weights: Weights::DEFAULT,
+    cancel: None,
+};
+
+/// A position for the background thread, with where its verdicts go.
+struct Job {
+    board: Board,
+    moves: Vec<Move>,
+    cancel: Arc<AtomicBool>,
+    verdicts: Sender<(usize, Verdict)>,
+}
+
+pub struct Analysis {
+    /// The position the moves belong to.
+    stacks: Vec<Stack>,
+    moves: Vec<(Move, Option<Verdict>)>,
+    verdicts: Receiver<(usize, Verdict)>,
+    /// Set once the position is no longer wanted, which ends its search.
+    cancel: Arc<AtomicBool>,
+    jobs: Sender<Job>,
+}
+
+impl Analysis {
+    /// Starts the background thread and evaluating the moves of the position on the table. The
+    /// thread ends once the analysis is dropped.
+    pub fn start(game: &Game) -> Analysis {
+        let (jobs, todo) = mpsc::channel();
+        thread::spawn(move || work(todo));
+        Analysis::queue(game, jobs)
+    }
+
+    /// Evaluates the moves of the position on the table instead, on the same thread.
+    pub fn restart(&mut self, game: &Game) {
+        *self = Analysis::queue(game, self.jobs.clone());
+    }
+
+    fn queue(game: &Game, jobs: Sender<Job>) -> Analysis {
+        let stacks = game.export();
+        let board = Board::from_stacks(stacks.clone());
+        let moves = board.legal_moves();
+        let (tx, verdicts) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        // the thread only ends with the last sender, which is in `jobs`
+        let _ = jobs.send(Job {
+            board,
+            moves: moves.clone(),
+            cancel: cancel.clone(),
+            verdicts: tx,
+        });
+        Analysis {
+            stacks,
+            moves: moves.into_iter().map(|m| (m, None)).collect(),
+            verdicts,
+            cancel,
+            jobs,
+        }
+    }
+
+    /// True while the table shows the position that was analysed.
+    pub fn is_current(&self, game: &Game) -> bool {
+        self.stacks == game.export()
+    }
+
+    /// Picks up the verdicts found since the last call.
+    pub fn update(&mut self) {
+        for (i, verdict) in self.verdicts.try_iter() {
+            self.moves[i].1 = Some(verdict);
+        }
+    }
+
+    pub fn view(&self, game: &Game) -> Vec<AnalysedMove> {
+        self.moves
+            .iter()
+            .map(|&(m, verdict)| AnalysedMove {
+                text: m.to_string(),
+                target: match m {
+                    Move::Button(color, _, _) => MoveTarget::Button(color),
+                    Move::Cards(t, _, _) => MoveTarget::Stack(game.stack_entity(t)),
+                },
+                verdict,
+            })
+            .collect()
+    }
+}
+
+impl Drop for Analysis {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The background thread: evaluates the moves of every position it gets that is still wanted.
+fn work(jobs: Receiver<Job>) {
+    for job in jobs {
+        let budget = Budget {
+            cancel: Some(job.cancel.clone()),
+            ..MOVE_BUDGET
+        };
+        for (i, m) in job.moves.iter().enumerate() {
+            if job.cancel.load(Ordering::Relaxed) {
+                break;
+            }
+            let verdict = evaluate(&job.board, m, &budget).unwrap();
+            if job.verdicts.send((i, verdict)).is_err() {
+                break;
+            }
+        }
+    }
+}
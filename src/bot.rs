@@ -0,0 +1,440 @@
+//! A server for bots (`--listen ADDR`), on a Unix domain socket (`unix:PATH`) or a localhost
+//! TCP port (`127.0.0.1:PORT`). Bots send one JSON object per line and get one reply per line.
+//! The `id` of a request, if any, is copied into its reply.
+//!
+//! ```text
+//! {"cmd":"board"}              {"ok":true,"stacks":[["5r","Dg"],...],"won":false}
+//! {"cmd":"moves"}              {"ok":true,"moves":["37","46:2","Dr",...]}
+//! {"cmd":"move","move":"37"}   {"ok":true,"legal":true,"won":false}
+//! {"cmd":"deal","deal":"12"}   {"ok":true,"deal":"<code>"}, the deal is optional
+//! {"cmd":"subscribe"}          {"ok":true}, followed by {"event":"cards_dropped",...} lines
+//! ```
+//!
+//! Failed requests get `{"ok":false,"error":"..."}`. Stacks and moves are written in the notation
+//! of `solitaire_core::notation`, stacks in board order. Requests are executed one at a time once
+//! the table is at rest, and moves are played with the mouse code paths, so the game animates
+//! whatever the bot does.
+
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::UnixListener;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use solitaire_core::board::{Board, N_STACKS};
+use solitaire_core::deal::Deal;
+use solitaire_core::json::Value;
+use solitaire_core::notation::{NotationError, card_name, color_name, parse_move, stack_name};
+use solitaire_core::rules::Move;
+
+use crate::cs::{GameEvent, entity_name};
+use crate::driver::{Driver, Outcome};
+use crate::game::Game;
+use crate::types::*;
+
+#[derive(Debug, PartialEq)]
+pub enum Address {
+    Unix(PathBuf),
+    Tcp(SocketAddr),
+}
+
+impl FromStr for Address {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Address, String> {
+        if let Some(path) = s.strip_prefix("unix:") {
+            return Ok(Address::Unix(PathBuf::from(path)));
+        }
+        let addr: SocketAddr = s.parse().map_err(|_| format!("invalid address '{}'", s))?;
+        if !addr.ip().is_loopback() {
+            return Err(format!("{} is not a localhost address", addr));
+        }
+        Ok(Address::Tcp(addr))
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Address::Unix(path) => write!(f, "unix:{}", path.display()),
+            Address::Tcp(addr) => write!(f, "{}", addr),
+        }
+    }
+}
+
+/// A line from a bot, with the channel to its connection.
+struct Request {
+    line: String,
+    reply: Sender<String>,
+}
+
+pub struct BotServer {
+    requests: Receiver<Request>,
+    driver: Driver,
+    // the request waiting for its move or deal to finish
+    waiting: Option<(Sender<String>, Option<Value>)>,
+    subscribers: Vec<Sender<String>>,
+}
+
+fn serve<S: Read + Write + Send + 'static>(stream: S, mut writer: S, requests: Sender<Request>) {
+    let (reply, replies) = mpsc::channel::<String>();
+    thread::spawn(move || {
+        for line in replies {
+            if writeln!(writer, "{}", line)
+                .and_then(|_| writer.flush())
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { break };
+        let request = Request {
+            line,
+            reply: reply.clone(),
+        };
+        if requests.send(request).is_err() {
+            break;
+        }
+    }
+}
+
+impl BotServer {
+    /// Starts listening, bots are accepted in the background.
+    pub fn listen(addr: &Address) -> io::Result<BotServer> {
+        let (tx, rx) = mpsc::channel();
+        match addr {
+            Address::Unix(path) => {
+                // a socket left behind by an earlier run
+                if let Ok(meta) = fs::symlink_metadata(path)
+                    && meta.file_type().is_socket()
+                {
+                    fs::remove_file(path)?;
+                }
+                let listener = UnixListener::bind(path)?;
+                thread::spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        let Ok(writer) = stream.try_clone() else {
+                            continue;
+                        };
+                        let tx = tx.clone();
+                        thread::spawn(move || serve(stream, writer, tx));
+                    }
+                });
+            }
+            Address::Tcp(addr) => {
+                let listener = TcpListener::bind(addr)?;
+                thread::spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        let Ok(writer) = stream.try_clone() else {
+                            continue;
+                        };
+                        let tx = tx.clone();
+                        thread::spawn(move || serve(stream, writer, tx));
+                    }
+                });
+            }
+        }
+        Ok(BotServer::new(rx))
+    }
+
+    fn new(requests: Receiver<Request>) -> BotServer {
+        BotServer {
+            requests,
+            driver: Driver::default(),
+            waiting: None,
+            subscribers: Vec::new(),
+        }
+    }
+
+    /// Answers requests while the game is at rest.
+    pub fn update(&mut self, game: &mut Game) {
+        if let Some(outcome) = self.driver.update(game)
+            && let Some((reply, id)) = self.waiting.take()
+        {
+            let value = match outcome {
+                Outcome::Dealt(deal) => Ok(vec![("deal", Value::from(deal.code()))]),
+                Outcome::Played { legal } => Ok(vec![
+                    ("legal", Value::from(legal)),
+                    ("won", Value::from(game.check_win_condition())),
+                ]),
+                Outcome::Mismatch(_) => Err("the table does not match the rules".to_owned()),
+            };
+            respond(&reply, id, value);
+        }
+
+        while self.driver.ready(game) {
+            let Ok(request) = self.requests.try_recv() else {
+                break;
+            };
+            self.execute(request, game);
+        }
+    }
+
+    fn execute(&mut self, request: Request, game: &mut Game) {
+        let value = match request.line.parse::<Value>() {
+            Ok(value) => value,
+            Err(e) => return respond(&request.reply, None, Err(e.to_string())),
+        };
+        let id = value.get("id").cloned();
+        let board = Board::from_stacks(game.export());
+
+        let result = match value.get("cmd").and_then(Value::as_str) {
+            Some("board") => Ok(vec![
+                ("stacks", stacks_value(board.stacks())),
+                ("won", Value::from(board.is_won())),
+            ]),
+            Some("moves") => {
+                let moves: Vec<_> = board.legal_moves().iter().map(|m| m.to_string()).collect();
+                Ok(vec![("moves", Value::from(moves))])
+            }
+            Some("move") => match value.get("move").and_then(Value::as_str) {
+                Some(token) => match parse_move(token, board.stacks()) {
+                    Ok(Move::Cards(to, from, n)) if board.stacks()[from].len() >= n => {
+                        self.driver.move_cards(game, from, to, n);
+                        self.waiting = Some((request.reply, id));
+                        return;
+                    }
+                    Ok(Move::Cards(_, from, _)) => {
+                        Err(format!("not enough cards on {}", stack_name(from)))
+                    }
+                    Ok(Move::Button(color, _, _)) => {
+                        self.driver.collapse(game, color);
+                        self.waiting = Some((request.reply, id));
+                        return;
+                    }
+                    Err(NotationError::Illegal(_)) => Ok(vec![
+                        ("legal", Value::from(false)),
+                        ("won", Value::from(board.is_won())),
+                    ]),
+                    Err(e) => Err(e.to_string()),
+                },
+                None => Err("missing move".to_owned()),
+            },
+            Some("deal") => {
+                let deal = match value.get("deal") {
+                    None | Some(Value::Null) => Ok(Deal::from_seed(Deal::random_seed())),
+                    Some(Value::String(s)) => s.parse().map_err(|e| format!("{}", e)),
+                    Some(v) => match v.as_u64() {
+                        Some(seed) => Ok(Deal::from_seed(seed)),
+                        None => Err("invalid deal".to_owned()),
+                    },
+                };
+                match deal {
+                    Ok(deal) => {
+                        self.driver.deal(game, deal);
+                        self.waiting = Some((request.reply, id));
+                        return;
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            Some("subscribe") => {
+                self.subscribers.push(request.reply.clone());
+                Ok(vec![])
+            }
+            Some(cmd) => Err(format!("unknown command '{}'", cmd)),
+            None => Err("missing command".to_owned()),
+        };
+        respond(&request.reply, id, result);
+    }
+
+    /// Sends an event of the game to the subscribed bots.
+    pub fn publish(&mut self, game: &Game, event: &GameEvent) {
+        if self.subscribers.is_empty() {
+            return;
+        }
+        let Some(value) = event_value(game, event) else {
+            return;
+        };
+        let line = value.to_string();
+        self.subscribers.retain(|s| s.send(line.clone()).is_ok());
+    }
+}
+
+fn respond(reply: &Sender<String>, id: Option<Value>, result: Result<Vec<(&str, Value)>, String>) {
+    let mut pairs = Vec::new();
+    if let Some(id) = id {
+        pairs.push(("id", id));
+    }
+    match result {
+        Ok(fields) => {
+            pairs.push(("ok", Value::from(true)));
+            pairs.extend(fields);
+        }
+        Err(e) => {
+            pairs.push(("ok", Value::from(false)));
+            pairs.push(("error", Value::from(e)));
+        }
+    }
+    // the bot may be gone already
+    let _ = reply.send(Value::object(pairs).to_string());
+}
+
+fn stacks_value(stacks: &[Stack]) -> Value {
+    Value::Array(
+        stacks
+            .iter()
+            .take(N_STACKS)
+            .map(|s| Value::from(s.iter().map(|&c| card_name(c)).collect::<Vec<_>>()))
+            .collect(),
+    )
+}
+
+/// Stacks by their name in the notation, other entities such as dragged cards by id.
+fn stack_value(game: &Game, e: Entity) -> Value {
+    match game.stack_index(e) {
+        Some(idx) => Value::from(stack_name(idx).to_string()),
+        None => Value::from(entity_name(e)),
+    }
+}
+
+fn event_value(game: &Game, event: &GameEvent) -> Option<Value> {
+    let pairs = match *event {
+        GameEvent::CardPickedUp { from, n } => vec![
+            ("event", Value::from("card_picked_up")),
+            ("from", stack_value(game, from)),
+            ("n", Value::from(n)),
+        ],
+        GameEvent::CardsDropped { from, to, n } => vec![
+            ("event", Value::from("cards_dropped")),
+            ("from", stack_value(game, from)),
+            ("to", stack_value(game, to)),
+            ("n", Value::from(n)),
+        ],
+        GameEvent::DropRejected { from, n } => vec![
+            ("event", Value::from("drop_rejected")),
+            ("from", stack_value(game, from)),
+            ("n", Value::from(n)),
+        ],
+        GameEvent::AutoMoved { from, to, card } => vec![
+            ("event", Value::from("auto_moved")),
+            ("from", stack_value(game, from)),
+            ("to", stack_value(game, to)),
+            ("card", Value::from(card_name(card))),
+        ],
+        GameEvent::DragonsCollapsed(color) => vec![
+            ("event", Value::from("dragons_collapsed")),
+            ("color", Value::from(color_name(color).to_string())),
+        ],
+        GameEvent::AnimationFinished { target } => vec![
+            ("event", Value::from("animation_finished")),
+            (
+                "target",
+                target.map_or(Value::Null, |e| stack_value(game, e)),
+            ),
+        ],
+        GameEvent::GameWon => vec![("event", Value::from("game_won"))],
+        GameEvent::Sound(_) => return None,
+    };
+    Some(Value::object(pairs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn addresses() {
+        assert_eq!(
+            "unix:/tmp/solitaire.sock".parse(),
+            Ok(Address::Unix(PathBuf::from("/tmp/solitaire.sock")))
+        );
+        assert_eq!(
+            "127.0.0.1:7777".parse(),
+            Ok(Address::Tcp("127.0.0.1:7777".parse().unwrap()))
+        );
+        assert!("0.0.0.0:7777".parse::<Address>().is_err());
+        assert!("localhost".parse::<Address>().is_err());
+    }
+
+    #[test]
+    fn socket_lines() {
+        use std::os::unix::net::UnixStream;
+
+        let (bot, server) = UnixStream::pair().unwrap();
+        let writer = server.try_clone().unwrap();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || serve(server, writer, tx));
+
+        writeln!(&bot, r#"{{"cmd":"board"}}"#).unwrap();
+        let request = rx.recv().unwrap();
+        assert_eq!(request.line, r#"{"cmd":"board"}"#);
+        request.reply.send("{\"ok\":true}".to_owned()).unwrap();
+
+        let mut line = String::new();
+        BufReader::new(&bot).read_line(&mut line).unwrap();
+        assert_eq!(line, "{\"ok\":true}\n");
+    }
+
+    #[test]
+    fn bot_session() {
+        let deal = Deal::from_seed(3);
+        let board = Board::new(&deal);
+        let m = board
+            .legal_moves()
+            .into_iter()
+            .find(|m| matches!(m, Move::Cards(t, s, 1) if *s < 8 && *t < 8))
+            .unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let (reply, replies) = mpsc::channel();
+        let requests = [
+            r#"{"cmd":"subscribe"}"#.to_owned(),
+            r#"{"id":1,"cmd":"deal","deal":3}"#.to_owned(),
+            r#"{"id":2,"cmd":"board"}"#.to_owned(),
+            format!(r#"{{"id":3,"cmd":"move","move":"{}"}}"#, m),
+            r#"{"id":4,"cmd":"move","move":"Dr"}"#.to_owned(),
+            r#"{"cmd":"jump"}"#.to_owned(),
+            "not json".to_owned(),
+        ];
+        for line in requests {
+            let reply = reply.clone();
+            tx.send(Request { line, reply }).unwrap();
+        }
+
+        let mut server = BotServer::new(rx);
+        let mut game = Game::new();
+        for _ in 0..2000 {
+            server.update(&mut game);
+            game.state.run_update(0.05);
+            let events: Vec<_> = game.state.drain_events().collect();
+            for event in events {
+                server.publish(&game, &event);
+            }
+        }
+
+        let (events, lines): (Vec<Value>, Vec<Value>) = replies
+            .try_iter()
+            .map(|l| l.parse::<Value>().unwrap())
+            .partition(|v| v.get("event").is_some());
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[0].to_string(), r#"{"ok":true}"#);
+        assert_eq!(
+            lines[1].to_string(),
+            format!(r#"{{"id":1,"ok":true,"deal":"{}"}}"#, deal.code())
+        );
+        assert_eq!(lines[2].get("stacks"), Some(&stacks_value(board.stacks())));
+        assert_eq!(lines[3].get("legal"), Some(&Value::Bool(true)));
+        assert_eq!(lines[4].get("legal"), Some(&Value::Bool(false)));
+        assert_eq!(lines[5].get("ok"), Some(&Value::Bool(false)));
+        assert_eq!(lines[6].get("ok"), Some(&Value::Bool(false)));
+
+        let Move::Cards(t, from, _) = m else {
+            unreachable!()
+        };
+        let dropped = Value::object([
+            ("event", Value::from("cards_dropped")),
+            ("from", Value::from(stack_name(from).to_string())),
+            ("to", Value::from(stack_name(t).to_string())),
+            ("n", Value::from(1usize)),
+        ]);
+        assert!(events.contains(&dropped));
+    }
+}
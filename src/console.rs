@@ -0,0 +1,341 @@
+//! Text commands for scripted play, read from stdin (`--console`) or a script file
+//! (`--script FILE`), one command per line:
+//!
+//! ```text
+//! deal [seed or code]    start a new game
+//! move <from> <to> [n]   drag the top n cards (default 1) from one stack to another
+//! collapse <color>       click the dragon button of a color
+//! undo                   take back the last deal, move or collapse of the console
+//! dump                   print the table
+//! solve                  print the verdict of the solver and its solution
+//! quit                   close the game
+//! ```
+//!
+//! Stacks are named as in `solitaire_core::notation`. Moves and collapses are played with the
+//! mouse code paths and checked against the rules once the table is at rest, printing `ok`,
+//! `rejected` for illegal moves or an error if the table does not match the rules.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use solitaire_core::ai::{AiResult, AiState, Budget};
+use solitaire_core::board::{Board, N_STACKS};
+use solitaire_core::deal::Deal;
+use solitaire_core::notation::{card_name, parse_color, stack_index, stack_name};
+
+use crate::driver::{Driver, Outcome};
+use crate::game::Game;
+use crate::types::*;
+
+const SOLVER_NODES: usize = 100_000;
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Deal(Option<Deal>),
+    Move(usize, usize, usize),
+    Collapse(Color),
+    Undo,
+    Dump,
+    Solve,
+    Quit,
+}
+
+fn parse_stack(token: Option<&str>) -> Result<usize, String> {
+    let token = token.ok_or("missing stack")?;
+    let mut chars = token.chars();
+    match (chars.next().and_then(stack_index), chars.next()) {
+        (Some(idx), None) => Ok(idx),
+        _ => Err(format!("invalid stack '{}'", token)),
+    }
+}
+
+/// Parses a line of input, `None` for blank lines and comments.
+pub fn parse_command(line: &str) -> Result<Option<Command>, String> {
+    let line = line.split('#').next().unwrap();
+    let mut tokens = line.split_whitespace();
+    let Some(keyword) = tokens.next() else {
+        return Ok(None);
+    };
+
+    let command = match keyword {
+        "deal" => match tokens.next() {
+            Some(code) => Command::Deal(Some(code.parse().map_err(|e| format!("{}", e))?)),
+            None => Command::Deal(None),
+        },
+        "move" => {
+            let from = parse_stack(tokens.next())?;
+            let to = parse_stack(tokens.next())?;
+            let n = match tokens.next() {
+                Some(n) => match n.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("invalid card count '{}'", n)),
+                },
+                None => 1,
+            };
+            Command::Move(from, to, n)
+        }
+        "collapse" => {
+            let color = tokens.next().ok_or("missing color")?;
+            Command::Collapse(parse_color(color).ok_or(format!("invalid color '{}'", color))?)
+        }
+        "undo" => Command::Undo,
+        "dump" => Command::Dump,
+        "solve" => Command::Solve,
+        "quit" => Command::Quit,
+        _ => return Err(format!("unknown command '{}'", keyword)),
+    };
+
+    match tokens.next() {
+        Some(token) => Err(format!("unexpected '{}'", token)),
+        None => Ok(Some(command)),
+    }
+}
+
+pub struct Console {
+    lines: Receiver<String>,
+    out: Box<dyn Write>,
+    driver: Driver,
+    undo: Vec<String>,
+}
+
+impl Console {
+    fn spawn<R: BufRead>(reader: impl FnOnce() -> R + Send + 'static) -> Receiver<String> {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader().lines() {
+                let Ok(line) = line else { break };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        rx
+    }
+
+    pub fn stdin() -> Console {
+        Console::new(
+            Console::spawn(|| io::stdin().lock()),
+            Box::new(io::stdout()),
+        )
+    }
+
+    pub fn script<P: AsRef<Path>>(path: P) -> io::Result<Console> {
+        let file = File::open(path)?;
+        Ok(Console::new(
+            Console::spawn(move || BufReader::new(file)),
+            Box::new(io::stdout()),
+        ))
+    }
+
+    pub fn new(lines: Receiver<String>, out: Box<dyn Write>) -> Console {
+        Console {
+            lines,
+            out,
+            driver: Driver::default(),
+            undo: Vec::new(),
+        }
+    }
+
+    /// Runs the next command once the game is at rest. Returns false after `quit` or at the end
+    /// of the input.
+    pub fn update(&mut self, game: &mut Game) -> bool {
+        match self.driver.update(game) {
+            Some(Outcome::Dealt(deal)) => writeln!(self.out, "dealt {}", deal).unwrap(),
+            Some(Outcome::Played { legal }) => {
+                writeln!(self.out, "{}", if legal { "ok" } else { "rejected" }).unwrap()
+            }
+            Some(Outcome::Mismatch(table)) => {
+                writeln!(self.out, "error: the table does not match the rules").unwrap();
+                self.dump(&table);
+            }
+            None => {}
+        }
+        if !self.driver.ready(game) {
+            return true;
+        }
+
+        let line = match self.lines.try_recv() {
+            Ok(line) => line,
+            Err(TryRecvError::Empty) => return true,
+            Err(TryRecvError::Disconnected) => return false,
+        };
+        match parse_command(&line) {
+            Ok(Some(command)) => self.execute(command, game),
+            Ok(None) => true,
+            Err(e) => {
+                writeln!(self.out, "error: {}", e).unwrap();
+                true
+            }
+        }
+    }
+
+    fn execute(&mut self, command: Command, game: &mut Game) -> bool {
+        let board = Board::from_stacks(game.export());
+        match command {
+            Command::Deal(deal) => {
+                self.undo.push(game.snapshot());
+                let deal = deal.unwrap_or_else(|| Deal::from_seed(Deal::random_seed()));
+                self.driver.deal(game, deal);
+            }
+            Command::Move(from, to, n) => {
+                if board.stacks()[from].len() < n {
+                    writeln!(self.out, "error: not enough cards on {}", stack_name(from)).unwrap();
+                    return true;
+                }
+                self.undo.push(game.snapshot());
+                self.driver.move_cards(game, from, to, n);
+            }
+            Command::Collapse(color) => {
+                self.undo.push(game.snapshot());
+                self.driver.collapse(game, color);
+            }
+            Command::Undo => match self.undo.pop().map(|s| Game::restore(&s)) {
+                Some(Ok(restored)) => {
+                    *game = restored;
+                    writeln!(self.out, "ok").unwrap();
+                }
+                Some(Err(e)) => writeln!(self.out, "error: {}", e).unwrap(),
+                None => writeln!(self.out, "error: nothing to undo").unwrap(),
+            },
+            Command::Dump => self.dump(board.stacks()),
+            Command::Solve => {
+                let ai = AiState::new(board.stacks().to_vec());
+                match ai.astar(&Budget::nodes(SOLVER_NODES)) {
+                    AiResult::Winable(path) => {
+                        let moves: Vec<_> = board
+                            .player_moves(&path)
+                            .iter()
+                            .map(|m| m.to_string())
+                            .collect();
+                        writeln!(self.out, "winnable: {}", moves.join(" ")).unwrap();
+                    }
+                    AiResult::Lost => writeln!(self.out, "lost").unwrap(),
+                    AiResult::Unknown => writeln!(self.out, "unknown").unwrap(),
+                }
+            }
+            Command::Quit => return false,
+        }
+        true
+    }
+
+    fn dump(&mut self, stacks: &[Stack]) {
+        for (idx, stack) in stacks.iter().enumerate().take(N_STACKS) {
+            let cards: Vec<_> = stack.iter().map(|&c| card_name(c)).collect();
+            writeln!(self.out, "{}: {}", stack_name(idx), cards.join(" ")).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use solitaire_core::rules::Move;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Output(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn play_script(script: &str) -> (Game, String) {
+        let (tx, rx) = mpsc::channel();
+        for line in script.lines() {
+            tx.send(line.to_owned()).unwrap();
+        }
+        drop(tx);
+
+        let out = Output::default();
+        let mut console = Console::new(rx, Box::new(out.clone()));
+        let mut game = Game::new();
+        while console.update(&mut game) {
+            game.state.run_update(0.05);
+        }
+        let text = String::from_utf8(out.0.borrow().clone()).unwrap();
+        (game, text)
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(parse_command(" # comment"), Ok(None));
+        assert_eq!(
+            parse_command("move 3 7 2"),
+            Ok(Some(Command::Move(2, 6, 2)))
+        );
+        assert_eq!(parse_command("move 1 x"), Ok(Some(Command::Move(0, 12, 1))));
+        assert_eq!(
+            parse_command("collapse red"),
+            Ok(Some(Command::Collapse(Color::Red)))
+        );
+        assert_eq!(
+            parse_command("deal 12345"),
+            Ok(Some(Command::Deal(Some(Deal::from_seed(12345)))))
+        );
+        for line in [
+            "move 3",
+            "move 3 9",
+            "move 3 7 0",
+            "collapse blue",
+            "undo 2",
+            "jump",
+        ] {
+            assert!(parse_command(line).is_err(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn scripted_game() {
+        let deal = Deal::from_seed(1);
+        let mut board = Board::new(&deal);
+        let m = board
+            .legal_moves()
+            .into_iter()
+            .find(|m| matches!(m, Move::Cards(t, s, 1) if *s < 8 && *t < 8))
+            .unwrap();
+        let Move::Cards(t, s, _) = m else {
+            unreachable!()
+        };
+        let illegal = (0..8)
+            .find(|&i| i != s && !board.is_legal(&Move::Cards(i, s, 1)))
+            .unwrap();
+
+        let script = format!(
+            "deal 1\nmove {s} {illegal}\nmove {s} {t}\ndump\nundo\nundo\nundo\nundo\ncollapse w\n",
+            s = stack_name(s),
+            t = stack_name(t),
+            illegal = stack_name(illegal),
+        );
+        let (game, out) = play_script(&script);
+        let lines: Vec<_> = out.lines().collect();
+
+        assert_eq!(lines[0], format!("dealt {}", deal));
+        assert_eq!(&lines[1..3], ["rejected", "ok"]);
+        board.apply(&m).unwrap();
+        let dump = format!("{}: ", stack_name(t))
+            + &board.stacks()[t]
+                .iter()
+                .map(|&c| card_name(c))
+                .collect::<Vec<_>>()
+                .join(" ");
+        assert_eq!(lines[3 + t], dump);
+        assert_eq!(
+            &lines[18..],
+            ["ok", "ok", "ok", "error: nothing to undo", "rejected"]
+        );
+        // back at the shuffled deck before the deal
+        assert_eq!(game.export()[11].cards, [Suite::FaceDown; 40]);
+    }
+}
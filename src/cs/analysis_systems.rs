@@ -0,0 +1,113 @@
+use ggez::graphics::{self, Canvas, DrawMode, DrawParam, Mesh, MeshBuilder, Rect};
+use ggez::{Context, GameResult};
+
+use crate::ai::Verdict;
+use crate::types::*;
+
+use super::GameState;
+use super::debug_systems::{LABEL_SIZE, label, panel, rect};
+use super::input_systems::drop_target_box;
+
+/// Where a move of the analysis view ends.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MoveTarget {
+    Stack(Entity),
+    Button(Color),
+}
+
+/// A move of the analysis view and what the solver thinks of it, `None` while it searches.
+pub struct AnalysedMove {
+    pub text: String,
+    pub target: MoveTarget,
+    pub verdict: Option<Verdict>,
+}
+
+fn verdict_color(verdict: Option<Verdict>) -> graphics::Color {
+    match verdict {
+        Some(Verdict::Winnable(_)) => graphics::Color::new(0.2, 1.0, 0.2, 1.0),
+        Some(Verdict::Lost) => graphics::Color::new(1.0, 0.2, 0.2, 1.0),
+        Some(Verdict::Unknown) => graphics::Color::new(1.0, 0.9, 0.0, 1.0),
+        None => graphics::Color::new(1.0, 1.0, 1.0, 0.8),
+    }
+}
+
+impl GameState {
+    /// Outlines the target of every move in the colour of its verdict, green for winnable, red
+    /// for lost, yellow for unknown and white while the solver searches, and lists the moves in
+    /// the bottom left corner. Moves to the same target are nested inside each other. The lines
+    /// of `study` go into a panel next to the list.
+    pub fn analysis_render_system(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        moves: &[AnalysedMove],
+        study: &[String],
+    ) -> GameResult<()> {
+        let mut mesh = MeshBuilder::new();
+        let mut labels = Vec::new();
+        let mut seen: Vec<MoveTarget> = Vec::new();
+        for m in moves {
+            let nested = seen.iter().filter(|&&t| t == m.target).count() as f32;
+            seen.push(m.target);
+            let color = verdict_color(m.verdict);
+            match m.target {
+                MoveTarget::Stack(e) => {
+                    let (Some(p), Some(s)) = (self.get_position(e), self.get_stack(e)) else {
+                        continue;
+                    };
+                    let bb = drop_target_box(*p, s);
+                    let r = rect(&bb);
+                    let inset = 3.0 * nested;
+                    let r = Rect::new(
+                        r.x + inset,
+                        r.y + inset,
+                        r.w - 2.0 * inset,
+                        r.h - 2.0 * inset,
+                    );
+                    mesh.rectangle(DrawMode::stroke(2.0), r, color)?;
+                    labels.push((
+                        bb.topleft + Vector2::new(4.0, 4.0 + nested * (LABEL_SIZE + 2.0)),
+                        m.text.clone(),
+                        color,
+                    ));
+                }
+                MoveTarget::Button(c) => {
+                    let Some(p) = self.button_position(c) else {
+                        continue;
+                    };
+                    let radius = BUTTON_RADIUS + 3.0 + 3.0 * nested;
+                    mesh.circle(DrawMode::stroke(2.0), p, radius, 0.5, color)?;
+                }
+            }
+        }
+        if !seen.is_empty() {
+            canvas.draw(&Mesh::from_data(&ctx.gfx, mesh.build()), DrawParam::new());
+        }
+        for (pos, text, color) in labels {
+            canvas.draw(&label(&text), DrawParam::new().dest(pos).color(color));
+        }
+
+        let mut list: Vec<_> = moves
+            .iter()
+            .map(|m| {
+                let verdict = m.verdict.map_or("searching".to_owned(), |v| v.to_string());
+                (
+                    format!("{:<6} {}", m.text, verdict),
+                    verdict_color(m.verdict),
+                )
+            })
+            .collect();
+        if list.is_empty() {
+            list.push(("no moves".to_owned(), verdict_color(None)));
+        }
+        // above the win count
+        panel(ctx, canvas, 8.0, 776.0, &list)?;
+
+        if !study.is_empty() {
+            let white = verdict_color(None);
+            let study: Vec<_> = study.iter().map(|line| (line.clone(), white)).collect();
+            panel(ctx, canvas, 268.0, 776.0, &study)?;
+        }
+        Ok(())
+    }
+}
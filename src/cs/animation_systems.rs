@@ -1,22 +1,16 @@
 use crate::types::*;
-use crate::utils::all::All;
 
-use super::GameState;
-
-use crate::resources::Resources;
+use super::{GameEvent, GameState};
 
 impl GameState {
-    pub fn animation_update_system(&mut self, dt: f32, res: &mut Resources) -> bool {
+    pub fn animation_update_system(&mut self, dt: f32) -> bool {
         let mut busy = false;
 
         let mut finished = Vec::new();
 
         for (p, a, e) in self
-            .positions
-            .iter_mut()
-            .zip(self.animations.iter_mut())
-            .zip(self.entities.iter())
-            .filter_map(|x| x.all())
+            .components
+            .query::<(&mut Point2, &mut Animation, &Entity)>()
         {
             if a.time_left > 0.0 {
                 busy = true;
@@ -28,7 +22,7 @@ impl GameState {
                     dt
                 };
                 if a.sound_start != Sounds::None && a.start_delay <= 0.0 {
-                    res.play_sound(a.sound_start);
+                    self.events.push(GameEvent::Sound(a.sound_start));
                     a.sound_start = Sounds::None;
                 }
                 let time_step = dt.min(a.time_left);
@@ -36,19 +30,36 @@ impl GameState {
                 a.time_left -= dt;
             } else {
                 finished.push(*e);
-                res.play_sound(a.sound_stop);
+                if a.sound_stop != Sounds::None {
+                    self.events.push(GameEvent::Sound(a.sound_stop));
+                }
             }
         }
 
         for e in finished.into_iter() {
-            let idx = self.ent_lookup[&e];
-            if let Some(target) = self.animations[idx].take().unwrap().target_stack {
-                let tidx = self.ent_lookup[&target];
-                let card = self.stacks[idx].take().unwrap().pop_card().unwrap();
-                self.stacks[tidx].as_mut().unwrap().push_card(card);
-                self.dirty = true;
+            let Ok(idx) = self.index(e) else {
+                continue;
+            };
+            let target_stack = self.components.take::<Animation>(idx).unwrap().target_stack;
+            self.events.push(GameEvent::AnimationFinished {
+                target: target_stack,
+            });
+            // a card whose target stack is gone just disappears with its animation
+            if let Some(tidx) = target_stack.and_then(|t| self.index(t).ok()) {
+                let Some(card) = self
+                    .components
+                    .take::<Stack>(idx)
+                    .and_then(|mut s| s.pop_card())
+                else {
+                    let _ = self.remove_entity(e);
+                    continue;
+                };
+                if let Some(target) = self.components.get_mut::<Stack>(tidx) {
+                    target.push_card(card);
+                    self.dirty = true;
+                }
             }
-            self.remove_entity(e);
+            let _ = self.remove_entity(e);
         }
 
         busy
@@ -0,0 +1,158 @@
+use ggez::graphics::{
+    self, Canvas, DrawMode, DrawParam, Drawable, Mesh, MeshBuilder, PxScale, Rect, Text,
+};
+use ggez::{Context, GameResult};
+
+use crate::rules;
+use crate::types::*;
+use crate::utils::bbox::BoundingBox;
+
+use super::input_systems::{dragged_box, drop_target_box};
+use super::{GameState, ZOrder, entity_name};
+
+/// What the debug overlay shows besides the world itself.
+pub struct DebugInfo {
+    pub fps: f64,
+    pub verdict: Option<String>,
+}
+
+pub(super) const LABEL_SIZE: f32 = 14.0;
+
+pub(super) fn rect(bb: &BoundingBox) -> Rect {
+    Rect::new(
+        bb.topleft.x,
+        bb.topleft.y,
+        bb.bottomright.x - bb.topleft.x,
+        bb.bottomright.y - bb.topleft.y,
+    )
+}
+
+pub(super) fn label(text: &str) -> Text {
+    let mut text = Text::new(text);
+    text.set_scale(PxScale::from(LABEL_SIZE));
+    text
+}
+
+/// Draws lines of text on a dark backdrop, growing up from `bottom`.
+pub(super) fn panel(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    left: f32,
+    bottom: f32,
+    lines: &[(String, graphics::Color)],
+) -> GameResult<()> {
+    let line_height = LABEL_SIZE + 4.0;
+    let corner = Point2::new(left, bottom - lines.len() as f32 * line_height - 8.0);
+    let width = lines
+        .iter()
+        .map(|(line, _)| label(line).dimensions(&ctx.gfx).w)
+        .fold(236.0, f32::max);
+    let backdrop = Mesh::new_rectangle(
+        &ctx.gfx,
+        DrawMode::fill(),
+        Rect::new(
+            corner.x - 4.0,
+            corner.y - 4.0,
+            width + 8.0,
+            bottom - corner.y,
+        ),
+        graphics::Color::new(0.0, 0.0, 0.0, 0.7),
+    )?;
+    canvas.draw(&backdrop, DrawParam::new());
+    for (i, (line, color)) in lines.iter().enumerate() {
+        let pos = corner + Vector2::new(0.0, i as f32 * line_height);
+        canvas.draw(&label(line), DrawParam::new().dest(pos).color(*color));
+    }
+    Ok(())
+}
+
+impl GameState {
+    /// Draws the drop areas of all stacks, the button radii, entity ids, z-orders and the state of
+    /// the systems on top of the table.
+    ///
+    /// While dragging, the dragged cards are outlined in yellow and the drop areas they overlap
+    /// in green if the drop would be accepted and in red if the rules reject it.
+    pub fn debug_render_system(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        info: &DebugInfo,
+    ) -> GameResult<()> {
+        let white = graphics::Color::new(1.0, 1.0, 1.0, 0.8);
+        let yellow = graphics::Color::new(1.0, 0.9, 0.0, 1.0);
+        let green = graphics::Color::new(0.2, 1.0, 0.2, 1.0);
+        let red = graphics::Color::new(1.0, 0.2, 0.2, 1.0);
+
+        let dragged = self.drag_lock.and_then(|(_, d)| {
+            let pos = self.get_position(d)?;
+            Some((d, dragged_box(*pos), self.get_stack(d)?))
+        });
+
+        let mut mesh = MeshBuilder::new();
+        let mut labels = Vec::new();
+        for (p, s, e) in self.components.query_ref::<(&Point2, &Stack, &Entity)>() {
+            let z = self.get::<ZOrder>(*e).map_or(0.0, |z| z.0);
+            labels.push((*p, format!("{} z{}", entity_name(*e), z)));
+
+            let (bb, color) = match dragged {
+                Some((d, bb, _)) if d == *e => (bb, yellow),
+                Some((_, bb_drag, d_stack)) => {
+                    let bb = drop_target_box(*p, s);
+                    match bb.intersects(&bb_drag) {
+                        true if rules::is_valid_drop(s, d_stack) => (bb, green),
+                        true => (bb, red),
+                        false => (bb, white),
+                    }
+                }
+                None => (drop_target_box(*p, s), white),
+            };
+            mesh.rectangle(DrawMode::stroke(1.0), rect(&bb), color)?;
+        }
+
+        for (p, b, e) in self.components.query_ref::<(&Point2, &Button, &Entity)>() {
+            let color = match b.state {
+                ButtonState::Active => green,
+                ButtonState::Up => white,
+                ButtonState::Down => red,
+            };
+            mesh.circle(DrawMode::stroke(1.0), *p, BUTTON_RADIUS, 0.5, color)?;
+            labels.push((
+                p + Vector2::new(BUTTON_RADIUS, -BUTTON_RADIUS),
+                entity_name(*e),
+            ));
+        }
+
+        if !labels.is_empty() {
+            canvas.draw(&Mesh::from_data(&ctx.gfx, mesh.build()), DrawParam::new());
+        }
+        for (pos, text) in labels {
+            canvas.draw(
+                &label(&text),
+                DrawParam::new()
+                    .dest(pos + Vector2::new(2.0, 2.0))
+                    .color(yellow),
+            );
+        }
+
+        let animations = self
+            .components
+            .column::<Animation>()
+            .iter()
+            .flatten()
+            .count();
+        let drag = match self.drag_lock {
+            Some((src, drg)) => format!("{} from {}", entity_name(drg), entity_name(src)),
+            None => "-".to_owned(),
+        };
+        let status = [
+            format!("fps {:.0}", info.fps),
+            format!("busy {} dirty {} won {}", self.busy, self.dirty, self.won),
+            format!("drag {}", drag),
+            format!("animations {}", animations),
+            format!("solver {}", info.verdict.as_deref().unwrap_or("-")),
+        ];
+        let status: Vec<_> = status.into_iter().map(|line| (line, yellow)).collect();
+        panel(ctx, canvas, 1040.0, 806.0, &status)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use crate::types::*;
+
+/// Something that happened in the game, published by the systems.
+///
+/// Events are queued in the `GameState` and drained once per frame by whoever runs the game,
+/// so audio, statistics and the like can react without the systems knowing about them.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GameEvent {
+    /// The player picked up `n` cards from a stack.
+    CardPickedUp { from: Entity, n: usize },
+    /// The player dropped `n` cards onto a stack.
+    CardsDropped { from: Entity, to: Entity, n: usize },
+    /// The dragged cards did not fit anywhere and went back to their stack.
+    DropRejected { from: Entity, n: usize },
+    /// A card started moving automatically.
+    AutoMoved {
+        from: Entity,
+        to: Entity,
+        card: Suite,
+    },
+    /// The player collapsed the four dragons of a color.
+    DragonsCollapsed(Color),
+    /// A card arrived at the end of its animation.
+    AnimationFinished { target: Option<Entity> },
+    /// All cards are on the foundations. Published once per game.
+    GameWon,
+    /// A sound cue of an animation, timed to its start or end.
+    Sound(Sounds),
+}
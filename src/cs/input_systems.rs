@@ -1,11 +1,21 @@
 use crate::rules;
 use crate::types::*;
-use crate::utils::all::All;
 use crate::utils::bbox::BoundingBox;
 
-use super::GameState;
+use super::{GameEvent, GameState};
 
-use crate::resources::Resources;
+/// The area of the dragged cards that has to overlap a drop target.
+pub fn dragged_box(pos: Point2) -> BoundingBox {
+    BoundingBox::new(pos.x, pos.x + CARD_WIDTH, pos.y, pos.y + CARD_HEIGHT)
+}
+
+/// The area of a stack that accepts dropped cards, covering all of its cards.
+pub fn drop_target_box(pos: Point2, stack: &Stack) -> BoundingBox {
+    let q = pos
+        + stack.get_stackshift() * (stack.len() as f32 - 1.0).max(0.0)
+        + Vector2::new(CARD_WIDTH, CARD_HEIGHT);
+    BoundingBox::new(pos.x, q.x, pos.y, q.y)
+}
 
 impl GameState {
     pub fn button_click_system(&mut self, click_pos: Point2) {
@@ -13,52 +23,53 @@ impl GameState {
             return;
         }
 
-        let mut animation = Vec::new();
-        {
-            let compound_iterator = self
-                .positions
+        let clicked = self
+            .components
+            .query_ref::<(&Point2, &Button, &Entity)>()
+            .find(|(p, b, _)| {
+                b.state == ButtonState::Active
+                    && (click_pos - *p).norm_squared() <= BUTTON_RADIUS_SQUARED
+            })
+            .map(|(_, b, e)| (*e, b.color, b.stacks));
+        let Some((button, color, Some((target_stack, source_stacks)))) = clicked else {
+            return;
+        };
+        let (Ok(t), Ok(sources)) = (
+            self.index(target_stack),
+            source_stacks
                 .iter()
-                .zip(self.buttons.iter_mut())
-                .filter_map(|x| x.all())
-                .filter(|&(_, ref b)| b.state == ButtonState::Active);
-            'outer: for (p, b) in compound_iterator {
-                let dist = click_pos - p;
-                if dist.norm_squared() <= BUTTON_RADIUS_SQUARED {
-                    b.state = ButtonState::Down;
-                    let (target_stack, source_stacks) = b.stacks.unwrap();
-                    let t = self.ent_lookup[&target_stack];
-                    let target_pos = self.positions[t].unwrap();
-                    let mut sound_start = Sounds::Sweep;
-                    for e in source_stacks.iter() {
-                        let s = self.ent_lookup[&e];
-                        let stack = self.stacks[s].as_mut().unwrap();
-                        let pos = &self.positions[s].unwrap();
-
-                        let start_pos = pos + stack.get_stackshift() * (stack.len() - 1) as f32;
-                        stack.pop_card();
-
-                        let ani = Animation {
-                            target_pos,
-                            target_stack: Some(target_stack),
-                            start_delay: 0.0,
-                            time_left: 0.3,
-                            sound_start,
-                            sound_stop: Sounds::None,
-                        };
-                        animation.push((start_pos, ani));
-                        sound_start = Sounds::None; // play only one sound for all cards
-                    }
-                    break 'outer;
-                }
-            }
-        }
-        for (start_pos, ani) in animation {
+                .map(|&e| self.index(e))
+                .collect::<Result<Vec<_>, _>>(),
+        ) else {
+            return; // the stacks are gone, wait for the next button update
+        };
+
+        self.get_mut::<Button>(button).unwrap().state = ButtonState::Down;
+        self.events.push(GameEvent::DragonsCollapsed(color));
+        let target_pos = *self.components.get::<Point2>(t).unwrap();
+        let mut sound_start = Sounds::Sweep;
+        for s in sources {
+            let pos = *self.components.get::<Point2>(s).unwrap();
+            let stack = self.components.get_mut::<Stack>(s).unwrap();
+
+            let start_pos = pos + stack.get_stackshift() * (stack.len() - 1) as f32;
+            stack.pop_card();
+
+            let ani = Animation {
+                target_pos,
+                target_stack: Some(target_stack),
+                start_delay: 0.0,
+                time_left: 0.3,
+                sound_start,
+                sound_stop: Sounds::None,
+            };
             self.animate(Suite::FaceDown, start_pos, 100.0, ani);
-            self.dirty = true;
+            sound_start = Sounds::None; // play only one sound for all cards
         }
+        self.dirty = true;
     }
 
-    pub fn begin_drag_system(&mut self, mouse_pos: Point2, res: &mut Resources) {
+    pub fn begin_drag_system(&mut self, mouse_pos: Point2) {
         if self.busy() {
             return;
         }
@@ -69,12 +80,7 @@ impl GameState {
 
         let mut hit = None;
         {
-            let compound_iterator = self
-                .positions
-                .iter()
-                .zip(self.stacks.iter_mut())
-                .zip(self.entities.iter())
-                .filter_map(|x| x.all());
+            let compound_iterator = self.components.query::<(&Point2, &mut Stack, &Entity)>();
 
             'outer: for (p, s, e) in compound_iterator {
                 if mouse_pos.x < p.x || mouse_pos.y < p.y {
@@ -90,9 +96,12 @@ impl GameState {
                     {
                         if rules::is_valid_drag(s, i) {
                             let substack = s.split(i);
+                            self.events.push(GameEvent::CardPickedUp {
+                                from: *e,
+                                n: substack.len(),
+                            });
                             hit = Some((card_pos, substack, *e));
                         }
-                        res.pickup_sound.play().unwrap();
                         break 'outer; // there can be only one
                     }
                 }
@@ -121,48 +130,47 @@ impl GameState {
         }
     }
 
-    pub fn done_drag_system(&mut self, res: &mut Resources) {
+    pub fn done_drag_system(&mut self) {
         if self.busy() {
             return;
         }
 
         if let Some((src, drg)) = self.drag_lock.take() {
-            res.place_sound.play().unwrap();
-
-            let idx = self.ent_lookup[&drg];
-            let mut d_stack = self.stacks[idx].take();
-            let pos = self.positions[idx].take().unwrap();
+            let Ok(idx) = self.index(drg) else {
+                return;
+            };
+            let mut d_stack = self.components.take::<Stack>(idx);
+            let n = d_stack.as_ref().unwrap().len();
+            let pos = self.components.take::<Point2>(idx).unwrap();
 
-            let bb_drag = BoundingBox::new(pos.x, pos.x + CARD_WIDTH, pos.y, pos.y + CARD_HEIGHT);
+            let bb_drag = dragged_box(pos);
 
             {
-                let compound_iterator = self
-                    .positions
-                    .iter()
-                    .zip(self.stacks.iter_mut())
-                    .filter_map(|x| x.all());
-                for (p, s) in compound_iterator {
-                    let q = p
-                        + s.get_stackshift() * (s.len() as f32 - 1.0).max(0.0)
-                        + Vector2::new(CARD_WIDTH, CARD_HEIGHT);
-                    let bb_target = BoundingBox::new(p.x, q.x, p.y, q.y);
-
-                    if bb_target.intersects(&bb_drag) {
-                        if rules::is_valid_drop(s, d_stack.as_ref().unwrap()) {
-                            s.extend(d_stack.take().unwrap());
-                            self.dirty = true;
-                            break;
-                        }
+                let compound_iterator = self.components.query::<(&Point2, &mut Stack, &Entity)>();
+                for (p, s, e) in compound_iterator {
+                    if drop_target_box(*p, s).intersects(&bb_drag)
+                        && rules::is_valid_drop(s, d_stack.as_ref().unwrap())
+                    {
+                        s.extend(d_stack.take().unwrap());
+                        self.events.push(GameEvent::CardsDropped {
+                            from: src,
+                            to: *e,
+                            n,
+                        });
+                        self.dirty = true;
+                        break;
                     }
                 }
             }
 
             if let Some(ds) = d_stack {
-                let stack = self.get_stack_mut(src).unwrap();
-                stack.extend(ds);
+                self.events.push(GameEvent::DropRejected { from: src, n });
+                if let Some(stack) = self.get_stack_mut(src) {
+                    stack.extend(ds);
+                }
             }
 
-            self.remove_entity(drg);
+            let _ = self.remove_entity(drg);
         }
     }
 }
@@ -0,0 +1,192 @@
+//! Consistency checks of the world, run after every update in debug builds.
+//!
+//! The cards on the table, in the hand and in flight must always be the 40 cards of a deck, with
+//! the dragons of a collapsed color replaced by four face down cards, or the 40 face down cards
+//! of the shuffled deck. Sorting columns, foundations and dragon cells must look like they were
+//! built by legal play.
+
+use std::collections::HashMap;
+
+use log::error;
+
+use solitaire_core::board::SORTING;
+use solitaire_core::deal::{DECK_SIZE, full_deck};
+use solitaire_core::notation::card_name;
+use solitaire_core::rules;
+
+use crate::types::*;
+
+use super::{GameState, entity_name};
+
+/// Number of events kept for the report of a violation.
+const HISTORY: usize = 32;
+
+/// Height of the columns after the deal. Anything above must have been played.
+const DEAL_HEIGHT: usize = DECK_SIZE / SORTING.end;
+
+const COLORS: [Color; 3] = [Color::Red, Color::Green, Color::White];
+
+fn count(cards: impl Iterator<Item = Suite>) -> HashMap<Suite, usize> {
+    let mut counts = HashMap::new();
+    for card in cards {
+        *counts.entry(card).or_insert(0) += 1;
+    }
+    counts
+}
+
+impl GameState {
+    /// Everything about the world that legal play cannot produce. Empty if all is well.
+    pub fn invariant_violations(&self) -> Vec<String> {
+        let mut violations: Vec<_> = self
+            .dangling_references()
+            .into_iter()
+            .map(|e| format!("dangling reference to entity {}", entity_name(e)))
+            .collect();
+        violations.extend(self.conservation_violations());
+
+        for (s, e) in self.components.query_ref::<(&Stack, &Entity)>() {
+            let name = entity_name(*e);
+            match s.role {
+                StackRole::Sorting => {
+                    if s.iter().any(|&c| c == Suite::FaceDown) {
+                        violations.push(format!("face down card on sorting stack {}", name));
+                    }
+                    // the last card that does not fit on the one below it
+                    let base = (1..s.len())
+                        .rev()
+                        .find(|&i| !rules::is_valid_pair(s.cards[i - 1], s.cards[i]))
+                        .unwrap_or(0);
+                    if base >= DEAL_HEIGHT {
+                        violations.push(format!(
+                            "sorting stack {} has {} on {} at height {}",
+                            name,
+                            card_name(s.cards[base]),
+                            card_name(s.cards[base - 1]),
+                            base
+                        ));
+                    }
+                }
+                StackRole::Target => {
+                    let color = match s.cards.first() {
+                        Some(&Suite::Number(_, c)) => Some(c),
+                        _ => None,
+                    };
+                    let ascending = s
+                        .iter()
+                        .enumerate()
+                        .all(|(i, &c)| Some(c) == color.map(|c| Suite::Number(i as u8 + 1, c)));
+                    if !ascending {
+                        violations.push(format!(
+                            "foundation {} is not an ascending run of one color",
+                            name
+                        ));
+                    }
+                }
+                StackRole::Dragon if s.iter().any(|&c| c == Suite::FaceDown) => {
+                    let arriving = self
+                        .components
+                        .query_ref::<(&Animation, &Stack)>()
+                        .filter(|(a, s)| {
+                            a.target_stack == Some(*e) && s.top() == Some(Suite::FaceDown)
+                        })
+                        .count();
+                    if s.iter().any(|&c| c != Suite::FaceDown) || s.len() + arriving != 4 {
+                        violations.push(format!(
+                            "collapsed dragon cell {} holds {} face down cards and {} other cards",
+                            name,
+                            s.len() + arriving,
+                            s.iter().filter(|&&c| c != Suite::FaceDown).count()
+                        ));
+                    }
+                }
+                StackRole::Dragon if s.len() > 1 => {
+                    violations.push(format!("dragon cell {} holds {} cards", name, s.len()));
+                }
+                _ => {}
+            }
+        }
+        violations
+    }
+
+    /// Differences between the cards in the world and a deck.
+    fn conservation_violations(&self) -> Vec<String> {
+        // cards flying off the table after a victory or when giving up
+        let leaving = self
+            .components
+            .column::<Animation>()
+            .iter()
+            .flatten()
+            .any(|a| a.target_stack.is_none_or(|t| !self.contains(t)));
+        if leaving {
+            return Vec::new();
+        }
+
+        let cards = count(
+            self.components
+                .column::<Stack>()
+                .iter()
+                .flatten()
+                .flat_map(|s| s.iter().copied()),
+        );
+        let total: usize = cards.values().sum();
+        if total == 0 || cards.get(&Suite::FaceDown) == Some(&DECK_SIZE) {
+            return Vec::new(); // an empty table or the shuffled deck
+        }
+
+        let collapsed: Vec<_> = COLORS
+            .iter()
+            .filter(|&&c| !cards.contains_key(&Suite::Dragon(c)))
+            .collect();
+        let expected = count(full_deck().into_iter().map(|c| match c {
+            Suite::Dragon(color) if collapsed.contains(&&color) => Suite::FaceDown,
+            c => c,
+        }));
+
+        let mut violations = Vec::new();
+        for card in expected
+            .keys()
+            .chain(cards.keys().filter(|c| !expected.contains_key(c)))
+        {
+            let want = expected.get(card).copied().unwrap_or(0);
+            let have = cards.get(card).copied().unwrap_or(0);
+            if want != have {
+                violations.push(format!(
+                    "{} copies of {} instead of {}",
+                    have,
+                    card_name(*card),
+                    want
+                ));
+            }
+        }
+        violations.sort();
+        violations
+    }
+
+    /// Logs the recent events and the world and panics if an invariant does not hold.
+    pub(super) fn check_invariants(&mut self) {
+        for &event in &self.events[self.recorded..] {
+            if self.history.len() == HISTORY {
+                self.history.pop_front();
+            }
+            self.history.push_back(event);
+        }
+        self.recorded = self.events.len();
+
+        #[cfg(test)]
+        if self.unchecked {
+            return;
+        }
+        let violations = self.invariant_violations();
+        if violations.is_empty() {
+            return;
+        }
+        for v in &violations {
+            error!("invariant violated: {}", v);
+        }
+        for event in &self.history {
+            error!("recent event: {:?}", event);
+        }
+        error!("world:\n{}", self.snapshot());
+        panic!("invariants violated: {}", violations.join(", "));
+    }
+}
@@ -1,39 +1,79 @@
-use std::collections::HashMap;
-use std::slice;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::vec;
 
 use ggez::graphics::Canvas;
 use ggez::{Context, GameResult};
 
+mod analysis_systems;
 mod animation_systems;
+mod debug_systems;
+mod events;
 mod input_systems;
+mod invariants;
 mod render_systems;
 mod rule_systems;
+mod snapshot;
+pub mod storage;
+#[cfg(test)]
+mod tests;
 
 use crate::resources::Resources;
 use crate::types::*;
 
+pub use self::analysis_systems::{AnalysedMove, MoveTarget};
+pub use self::debug_systems::DebugInfo;
+pub use self::events::GameEvent;
 use self::render_systems::*;
+pub use self::snapshot::{SnapshotError, entity_name, parse_entity};
+use self::storage::Components;
+
+/// Drawing order of an entity, higher is drawn later.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ZOrder(pub f32);
+
+/// An entity handle that does not refer to a live entity, because it was removed or the world
+/// was cleared since.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StaleEntity(pub Entity);
+
+impl fmt::Display for StaleEntity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "entity {} of generation {} does not exist",
+            self.0.id(),
+            self.0.generation()
+        )
+    }
+}
 
-type Component<T> = Vec<Option<T>>;
+impl std::error::Error for StaleEntity {}
 
 #[derive(Default)]
 pub struct GameState {
-    entities: Vec<Entity>,
     ent_lookup: HashMap<Entity, usize>,
-
-    stacks: Component<Stack>,
-    positions: Component<Point2>,
-    zorder: Component<f32>,
-    buttons: Component<Button>,
-    animations: Component<Animation>,
+    components: Components,
 
     next_id: usize,
+    generation: u32,
 
     busy: bool,
     dirty: bool,
     render_queue: RenderQueue,
 
     drag_lock: Option<(Entity, Entity)>,
+
+    won: bool,
+
+    // published by the systems, consumed by whoever runs the game
+    events: Vec<GameEvent>,
+    // the last events, for reports of broken invariants
+    history: VecDeque<GameEvent>,
+    recorded: usize,
+    // set by tests that build positions which cannot be reached in play
+    #[cfg(test)]
+    unchecked: bool,
 }
 
 impl GameState {
@@ -41,73 +81,134 @@ impl GameState {
         EntityBuilder::new(self)
     }
 
-    pub fn remove_entity(&mut self, id: Entity) {
-        let idx = self.ent_lookup[&id];
-
-        // last entity takes the place of id, so we need to update the lookup
-        self.ent_lookup.insert(*self.entities.last().unwrap(), idx);
+    pub fn remove_entity(&mut self, id: Entity) -> Result<(), StaleEntity> {
+        let idx = self.index(id)?;
         self.ent_lookup.remove(&id);
+        self.components.swap_remove_row(idx);
 
-        self.stacks.swap_remove(idx);
-        self.positions.swap_remove(idx);
-        self.zorder.swap_remove(idx);
-        self.buttons.swap_remove(idx);
-        self.animations.swap_remove(idx);
-
-        self.entities.swap_remove(idx);
+        // the last entity took the place of id, so we need to update the lookup
+        if let Some(&moved) = self.components.get::<Entity>(idx) {
+            self.ent_lookup.insert(moved, idx);
+        }
+        Ok(())
     }
 
     pub fn clear(&mut self) {
-        self.entities.clear();
         self.ent_lookup.clear();
-
-        self.stacks.clear();
-        self.positions.clear();
-        self.zorder.clear();
-        self.buttons.clear();
-        self.animations.clear();
+        self.components.clear();
 
         self.next_id = 0;
+        self.generation = self.generation.wrapping_add(1);
 
         self.busy = false;
         self.dirty = false;
 
         self.drag_lock = None;
+        self.won = false;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.components.column::<Entity>().iter().flatten().copied()
+    }
+
+    pub fn index(&self, id: Entity) -> Result<usize, StaleEntity> {
+        self.ent_lookup.get(&id).copied().ok_or(StaleEntity(id))
     }
 
-    pub fn iter(&self) -> slice::Iter<'_, Entity> {
-        self.entities.iter()
+    pub fn contains(&self, id: Entity) -> bool {
+        self.ent_lookup.contains_key(&id)
+    }
+
+    pub fn get<T: 'static>(&self, id: Entity) -> Option<&T> {
+        self.components.get(self.index(id).ok()?)
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, id: Entity) -> Option<&mut T> {
+        let idx = self.index(id).ok()?;
+        self.components.get_mut(idx)
     }
 
     pub fn get_stack(&self, id: Entity) -> Option<&Stack> {
-        let idx = self.ent_lookup[&id];
-        self.stacks[idx].as_ref()
+        self.get(id)
     }
 
     pub fn get_stack_mut(&mut self, id: Entity) -> Option<&mut Stack> {
-        let idx = self.ent_lookup[&id];
-        self.stacks[idx].as_mut()
+        self.get_mut(id)
     }
 
     pub fn get_position(&self, id: Entity) -> Option<&Point2> {
-        let idx = self.ent_lookup[&id];
-        self.positions[idx].as_ref()
+        self.get(id)
     }
 
     pub fn get_position_mut(&mut self, id: Entity) -> Option<&mut Point2> {
-        let idx = self.ent_lookup[&id];
-        self.positions[idx].as_mut()
+        self.get_mut(id)
+    }
+
+    /// Entities referenced by buttons and animations that do not exist any more.
+    pub fn dangling_references(&self) -> Vec<Entity> {
+        let buttons = self
+            .components
+            .column::<Button>()
+            .iter()
+            .flatten()
+            .filter_map(|b| b.stacks)
+            .flat_map(|(t, s)| std::iter::once(t).chain(s));
+        let animations = self
+            .components
+            .column::<Animation>()
+            .iter()
+            .flatten()
+            .filter_map(|a| a.target_stack);
+        buttons
+            .chain(animations)
+            .filter(|&e| !self.contains(e))
+            .collect()
+    }
+
+    /// Where the card at `idx` of a stack is drawn, or would be drawn if there was one.
+    pub fn card_position(&self, id: Entity, idx: usize) -> Option<Point2> {
+        let pos = self.get_position(id)?;
+        let stack = self.get_stack(id)?;
+        Some(pos + stack.get_stackshift() * idx as f32)
+    }
+
+    pub fn button_position(&self, color: Color) -> Option<Point2> {
+        self.components
+            .query_ref::<(&Point2, &Button)>()
+            .find(|(_, b)| b.color == color)
+            .map(|(p, _)| *p)
     }
 
     pub fn busy(&self) -> bool {
         self.busy
     }
 
-    pub fn run_update(&mut self, dt: f32, res: &mut Resources) -> bool {
+    /// True if nothing moves and no automatic move is pending. Unlike `busy` this also covers
+    /// animations started during the last update.
+    pub fn idle(&self) -> bool {
+        !self.busy
+            && !self.dirty
+            && self
+                .components
+                .column::<Animation>()
+                .iter()
+                .all(|a| a.is_none())
+    }
+
+    pub fn drain_events(&mut self) -> vec::Drain<'_, GameEvent> {
+        self.recorded = 0;
+        self.events.drain(..)
+    }
+
+    pub fn run_update(&mut self, dt: f32) -> bool {
         self.busy = false;
-        self.busy |= self.animation_update_system(dt, res);
+        self.busy |= self.animation_update_system(dt);
         self.button_update_system();
         self.auto_move_system();
+        self.win_check_system();
+        if cfg!(debug_assertions) {
+            self.check_invariants();
+        }
         self.busy
     }
 
@@ -120,21 +221,20 @@ impl GameState {
         self.render_queue
             .background_render_system(ctx, res, canvas)?;
         self.render_queue
-            .button_render_system(res, canvas, &self.positions, &self.buttons)?;
-        self.render_queue
-            .stack_render_system(&self.positions, &self.stacks, &self.zorder)?;
+            .button_render_system(res, canvas, &self.components)?;
+        self.render_queue.stack_render_system(&self.components)?;
         self.render_queue.render(ctx, res, canvas)?;
         Ok(())
     }
 
-    pub fn handle_mouse_button_down(&mut self, x: f32, y: f32, res: &mut Resources) {
+    pub fn handle_mouse_button_down(&mut self, x: f32, y: f32) {
         let pos = Point2::from([x, y]);
-        self.begin_drag_system(pos, res);
+        self.begin_drag_system(pos);
         self.button_click_system(pos);
     }
 
-    pub fn handle_mouse_button_up(&mut self, _x: f32, _y: f32, res: &mut Resources) {
-        self.done_drag_system(res);
+    pub fn handle_mouse_button_up(&mut self, _x: f32, _y: f32) {
+        self.done_drag_system();
     }
 
     pub fn handle_mouse_move(&mut self, xrel: f32, yrel: f32) {
@@ -153,66 +253,77 @@ impl GameState {
             .with_stack(stack)
             .build();
     }
+
+    /// Replaces the cards of stacks without animating them, to show another position of the
+    /// same game. The buttons of dragons that are collapsed in the position stay down.
+    pub fn set_stacks(&mut self, stacks: impl IntoIterator<Item = (Entity, Stack)>) {
+        for (e, stack) in stacks {
+            if let Some(s) = self.get_stack_mut(e) {
+                s.cards = stack.cards;
+            }
+        }
+        let cards: Vec<Suite> = self
+            .components
+            .column::<Stack>()
+            .iter()
+            .flatten()
+            .flat_map(|s| s.iter().copied())
+            .collect();
+        for (b,) in self.components.query::<(&mut Button,)>() {
+            b.state = match cards.contains(&Suite::Dragon(b.color)) {
+                true => ButtonState::Up,
+                false => ButtonState::Down,
+            };
+            b.stacks = None;
+        }
+        self.drag_lock = None;
+        self.dirty = true;
+    }
 }
 
+/// Creates an entity. Components are stored as they are added, `build` returns the handle.
 pub struct EntityBuilder<'a> {
     state: &'a mut GameState,
-    stack: Option<Stack>,
-    position: Option<Point2>,
-    zorder: Option<f32>,
-    button: Option<Button>,
-    animation: Option<Animation>,
+    id: Entity,
+    idx: usize,
 }
 
 impl<'a> EntityBuilder<'a> {
     fn new(state: &'a mut GameState) -> EntityBuilder<'a> {
-        EntityBuilder {
-            state,
-            stack: None,
-            position: None,
-            zorder: Some(0.0),
-            button: None,
-            animation: None,
-        }
+        let id = Entity::new(state.next_id, state.generation);
+        state.next_id += 1;
+        let idx = state.components.push_row();
+        state.ent_lookup.insert(id, idx);
+        EntityBuilder { state, id, idx }.with(id).with(ZOrder(0.0))
     }
 
     pub fn build(self) -> Entity {
-        let id = Entity::new(self.state.next_id);
-        let idx = self.state.entities.len();
-        self.state.entities.push(id);
-        self.state.ent_lookup.insert(id, idx);
-        self.state.next_id += 1;
-
-        self.state.stacks.push(self.stack);
-        self.state.positions.push(self.position);
-        self.state.zorder.push(self.zorder);
-        self.state.buttons.push(self.button);
-        self.state.animations.push(self.animation);
-        id
-    }
-
-    pub fn with_stack(mut self, value: Stack) -> EntityBuilder<'a> {
-        self.stack = Some(value);
-        self
+        self.id
     }
 
-    pub fn with_position(mut self, pos: Point2) -> EntityBuilder<'a> {
-        self.position = Some(pos);
+    /// Adds a component of any type.
+    pub fn with<T: 'static>(self, value: T) -> EntityBuilder<'a> {
+        self.state.components.insert(self.idx, value);
         self
     }
 
-    pub fn with_zorder(mut self, z: f32) -> EntityBuilder<'a> {
-        self.zorder = Some(z);
-        self
+    pub fn with_stack(self, value: Stack) -> EntityBuilder<'a> {
+        self.with(value)
     }
 
-    pub fn with_button(mut self, value: Button) -> EntityBuilder<'a> {
-        self.button = Some(value);
-        self
+    pub fn with_position(self, pos: Point2) -> EntityBuilder<'a> {
+        self.with(pos)
     }
 
-    pub fn with_animation(mut self, value: Animation) -> EntityBuilder<'a> {
-        self.animation = Some(value);
-        self
+    pub fn with_zorder(self, z: f32) -> EntityBuilder<'a> {
+        self.with(ZOrder(z))
+    }
+
+    pub fn with_button(self, value: Button) -> EntityBuilder<'a> {
+        self.with(value)
+    }
+
+    pub fn with_animation(self, value: Animation) -> EntityBuilder<'a> {
+        self.with(value)
     }
 }
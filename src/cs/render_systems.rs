@@ -8,9 +8,9 @@ use ggez::{Context, GameResult};
 
 use crate::resources::Resources;
 use crate::types::*;
-use crate::utils::all::All;
 
-use super::Component;
+use super::ZOrder;
+use super::storage::Components;
 
 enum DrawCommand {
     Card { z: f32, pos: Point2, suite: Suite },
@@ -49,7 +49,7 @@ impl RenderQueue {
         canvas.draw(&res.table_image, DrawParam::new());
 
         //graphics::set_color(ctx, graphics::Color::new(0.0, 0.0, 0.0, 1.0))?;
-        let txt = format!("Win Count: {}", res.wins());
+        let txt = format!("Win Count: {}", res.stats().won);
         let text = res.get_text(ctx, &txt)?;
         let pos = Point2::new(0.0, 806.0 - text.dimensions(ctx).h);
         //graphics::draw(ctx, text,pos, 0.0)?;
@@ -67,15 +67,10 @@ impl RenderQueue {
         &self,
         res: &Resources,
         canvas: &mut Canvas,
-        pos: &Component<Point2>,
-        btn: &Component<Button>,
+        components: &Components,
     ) -> GameResult<()> {
         //graphics::set_color(ctx, graphics::Color::new(1.0, 1.0, 1.0, 1.0))?;
-        for (p, b) in pos
-            .iter()
-            .zip(btn.iter())
-            .filter_map(|x| -> Option<(_, &Button)> { x.all() })
-        {
+        for (p, b) in components.query_ref::<(&Point2, &Button)>() {
             let img = &res.button_images[&(b.color, b.state)];
             //img.draw(ctx, p - Vector2::new(img.width() as f32, img.height() as f32) / 2.0, 0.0)?;
             let pos = p - Vector2::new(img.width() as f32, img.height() as f32) / 2.0;
@@ -85,18 +80,8 @@ impl RenderQueue {
         Ok(())
     }
 
-    pub fn stack_render_system(
-        &mut self,
-        pos: &Component<Point2>,
-        stk: &Component<Stack>,
-        zs: &Component<f32>,
-    ) -> GameResult<()> {
-        let compound_iterator = pos
-            .iter()
-            .zip(stk.iter())
-            .zip(zs.iter())
-            .filter_map(|x| -> Option<(_, _, &f32)> { x.all() });
-        for (p, s, &z) in compound_iterator {
+    pub fn stack_render_system(&mut self, components: &Components) -> GameResult<()> {
+        for (p, s, &ZOrder(z)) in components.query_ref::<(&Point2, &Stack, &ZOrder)>() {
             let mut pos = *p;
             let dpos = s.get_stackshift();
 
@@ -166,14 +151,14 @@ impl RenderQueue {
                     small_icon,
                     DrawParam::new()
                         .dest(pos + Vector2::new(-iw, -ih))
-                        .color(c.to_icon_color()),
+                        .color(icon_color(*c)),
                 );
                 canvas.draw(
                     small_icon,
                     DrawParam::new()
                         .dest(pos + Vector2::new(CARD_WIDTH + iw, CARD_HEIGHT + ih))
                         .rotation(f32::consts::PI)
-                        .color(c.to_icon_color()),
+                        .color(icon_color(*c)),
                 );
                 canvas.set_default_sampler();
 
@@ -184,7 +169,7 @@ impl RenderQueue {
                     large_icon,
                     DrawParam::new()
                         .dest(pos + Vector2::new(lw, lh))
-                        .color(c.to_icon_color()),
+                        .color(icon_color(*c)),
                 );
             }
             Suite::Number(i, ref c) => {
@@ -198,14 +183,14 @@ impl RenderQueue {
                     small_icon,
                     DrawParam::new()
                         .dest(pos + Vector2::new(-iw, -ih))
-                        .color(c.to_icon_color()),
+                        .color(icon_color(*c)),
                 );
                 canvas.draw(
                     small_icon,
                     DrawParam::new()
                         .dest(pos + Vector2::new(CARD_WIDTH + iw, CARD_HEIGHT + ih))
                         .rotation(f32::consts::PI)
-                        .color(c.to_icon_color()),
+                        .color(icon_color(*c)),
                 );
                 canvas.set_default_sampler();
 
@@ -216,7 +201,7 @@ impl RenderQueue {
                     large_icon,
                     DrawParam::new()
                         .dest(pos + Vector2::new(lw, lh))
-                        .color(c.to_icon_color()),
+                        .color(icon_color(*c)),
                 );
 
                 let nr = &res.numbers[i as usize - 1];
@@ -227,14 +212,14 @@ impl RenderQueue {
                     nr,
                     DrawParam::new()
                         .dest(pos + Vector2::new(-nw, -nh))
-                        .color(c.to_font_color()),
+                        .color(font_color(*c)),
                 );
                 canvas.draw(
                     nr,
                     DrawParam::new()
                         .dest(pos + Vector2::new(CARD_WIDTH + nw, CARD_HEIGHT + nh))
                         .rotation(f32::consts::PI)
-                        .color(c.to_font_color()),
+                        .color(font_color(*c)),
                 );
             }
         }
@@ -243,6 +228,22 @@ impl RenderQueue {
     }
 }
 
+fn font_color(color: Color) -> graphics::Color {
+    match color {
+        Color::Red => graphics::Color::new(0.7, 0.2, 0.1, 1.0),
+        Color::Green => graphics::Color::new(0.1, 0.4, 0.3, 1.0),
+        Color::White => graphics::Color::new(0.1, 0.1, 0.1, 1.0),
+    }
+}
+
+fn icon_color(color: Color) -> graphics::Color {
+    match color {
+        Color::Red => graphics::Color::new(1.0, 1.0, 1.0, 1.0),
+        Color::Green => graphics::Color::new(0.1, 0.4, 0.3, 1.0),
+        Color::White => graphics::Color::new(1.0, 1.0, 1.0, 1.0),
+    }
+}
+
 impl DrawCommand {
     fn get_z(&self) -> f32 {
         match *self {
@@ -259,6 +260,7 @@ impl cmp::Ord for DrawCommand {
     }
 }
 
+#[allow(clippy::non_canonical_partial_ord_impl)]
 impl cmp::PartialOrd for DrawCommand {
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
         other.get_z().partial_cmp(&self.get_z())
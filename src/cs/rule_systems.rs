@@ -1,8 +1,7 @@
 use crate::rules;
 use crate::types::*;
-use crate::utils::all::All;
 
-use super::GameState;
+use super::{GameEvent, GameState};
 
 impl GameState {
     pub fn button_update_system(&mut self) {
@@ -10,19 +9,22 @@ impl GameState {
             return;
         }
 
-        for b in self.buttons.iter_mut().filter_map(|x| x.all()) {
+        let (stacks, ents): (Vec<_>, Vec<Entity>) = self
+            .components
+            .query_ref::<(&Stack, &Entity)>()
+            .map(|(s, e)| (s, *e))
+            .unzip();
+        let results: Vec<_> = [Color::Red, Color::Green, Color::White]
+            .into_iter()
+            .map(|color| (color, rules::check_button(color, stacks.iter().copied())))
+            .collect();
+
+        for (b,) in self.components.query::<(&mut Button,)>() {
             if b.state == ButtonState::Down {
                 continue;
             }
 
-            let (stacks, ents): (Vec<_>, Vec<Entity>) = self
-                .stacks
-                .iter()
-                .zip(self.entities.iter())
-                .filter_map(|(stack, e)| stack.as_ref().map(|s| (s, e)))
-                .unzip();
-
-            let r = rules::check_button(b.color, stacks.into_iter());
+            let r = results.iter().find(|(c, _)| *c == b.color).unwrap().1;
 
             match r {
                 None => {
@@ -47,7 +49,8 @@ impl GameState {
         let auto_move;
         {
             let (stacks, idx): (Vec<_>, Vec<_>) = self
-                .stacks
+                .components
+                .column::<Stack>()
                 .iter()
                 .enumerate()
                 .filter_map(|(i, stack)| stack.as_ref().map(|s| (s, i)))
@@ -62,17 +65,17 @@ impl GameState {
             let target_pos;
             let target_stack;
             {
-                let s_stack = self.stacks[src].as_mut().unwrap();
+                let s_stack = self.components.get_mut::<Stack>(src).unwrap();
                 card = s_stack.pop_card().unwrap();
             }
             {
-                let s_stack = self.stacks[src].as_ref().unwrap();
-                let s_pos = self.positions[src].as_ref().unwrap();
+                let s_stack = self.components.get::<Stack>(src).unwrap();
+                let s_pos = self.components.get::<Point2>(src).unwrap();
                 start_pos = s_pos + s_stack.get_stackshift() * s_stack.len() as f32;
 
-                target_stack = Some(self.entities[dst]);
-                let t_stack = self.stacks[dst].as_ref().unwrap();
-                let t_pos = self.positions[dst].as_ref().unwrap();
+                target_stack = self.components.get::<Entity>(dst).copied();
+                let t_stack = self.components.get::<Stack>(dst).unwrap();
+                let t_pos = self.components.get::<Point2>(dst).unwrap();
                 target_pos = t_pos + t_stack.get_stackshift() * t_stack.len() as f32;
             }
 
@@ -84,7 +87,31 @@ impl GameState {
                 sound_start: Sounds::Sweep,
                 sound_stop: Sounds::None,
             };
+            self.events.push(GameEvent::AutoMoved {
+                from: self.components.get::<Entity>(src).copied().unwrap(),
+                to: target_stack.unwrap(),
+                card,
+            });
             self.animate(card, start_pos, 100.0, ani);
         }
     }
+
+    pub fn win_check_system(&mut self) {
+        if self.won || self.dirty || self.busy() {
+            return;
+        }
+
+        let mut stacks = self.components.column::<Stack>().iter().flatten();
+        let won = stacks.clone().any(|s| s.role == StackRole::Target)
+            && stacks.all(|s| match s.role {
+                StackRole::Sorting => s.is_empty(),
+                StackRole::Target => s.len() == 9,
+                _ => true,
+            });
+
+        if won {
+            self.won = true;
+            self.events.push(GameEvent::GameWon);
+        }
+    }
 }
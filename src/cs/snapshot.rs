@@ -0,0 +1,407 @@
+//! Text snapshots of the whole world.
+//!
+//! A snapshot is line based. The header names the format version and the state of the world,
+//! followed by one `entity` line per entity and one line per component of that entity:
+//!
+//! ```text
+//! solitaire-world 1
+//! world <next id> <generation> <busy> <dirty> <won>
+//! drag <source> <dragged> | drag -
+//! entity <id>:<generation>
+//! position <x> <y>
+//! zorder <z>
+//! stack <role> <cards...>
+//! button <color> <state> <target> <source> <source> <source> <source> | button <color> <state> -
+//! animation <x> <y> <target|-> <start delay> <time left> <start sound> <stop sound>
+//! ```
+//!
+//! Cards are written in the notation of `solitaire_core::notation`. Numbers are written so that
+//! they read back exactly, so a restored world continues animations where they were.
+
+use std::fmt;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use solitaire_core::notation::{card_name, color_name, parse_card, parse_color};
+
+use crate::types::*;
+
+use super::{GameState, ZOrder};
+
+const VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    Version(String),
+    Syntax(usize, String),
+    /// The snapshot parses, but the world it describes would break the systems.
+    Inconsistent(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Version(v) => write!(f, "unsupported snapshot version {}", v),
+            SnapshotError::Syntax(line, msg) => write!(f, "line {}: {}", line, msg),
+            SnapshotError::Inconsistent(msg) => write!(f, "inconsistent snapshot: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+fn role_name(role: StackRole) -> &'static str {
+    match role {
+        StackRole::Dragon => "dragon",
+        StackRole::Flower => "flower",
+        StackRole::Target => "target",
+        StackRole::Sorting => "sorting",
+        StackRole::Generic => "generic",
+        StackRole::Animation => "animation",
+    }
+}
+
+fn parse_role(name: &str) -> Option<StackRole> {
+    match name {
+        "dragon" => Some(StackRole::Dragon),
+        "flower" => Some(StackRole::Flower),
+        "target" => Some(StackRole::Target),
+        "sorting" => Some(StackRole::Sorting),
+        "generic" => Some(StackRole::Generic),
+        "animation" => Some(StackRole::Animation),
+        _ => None,
+    }
+}
+
+fn button_state_name(state: ButtonState) -> &'static str {
+    match state {
+        ButtonState::Active => "active",
+        ButtonState::Up => "up",
+        ButtonState::Down => "down",
+    }
+}
+
+fn parse_button_state(name: &str) -> Option<ButtonState> {
+    match name {
+        "active" => Some(ButtonState::Active),
+        "up" => Some(ButtonState::Up),
+        "down" => Some(ButtonState::Down),
+        _ => None,
+    }
+}
+
+fn sound_name(sound: Sounds) -> &'static str {
+    match sound {
+        Sounds::None => "none",
+        Sounds::Pickup => "pickup",
+        Sounds::Place => "place",
+        Sounds::Sweep => "sweep",
+        Sounds::Deal => "deal",
+    }
+}
+
+fn parse_sound(name: &str) -> Option<Sounds> {
+    match name {
+        "none" => Some(Sounds::None),
+        "pickup" => Some(Sounds::Pickup),
+        "place" => Some(Sounds::Place),
+        "sweep" => Some(Sounds::Sweep),
+        "deal" => Some(Sounds::Deal),
+        _ => None,
+    }
+}
+
+pub fn entity_name(e: Entity) -> String {
+    format!("{}:{}", e.id(), e.generation())
+}
+
+pub fn parse_entity(token: &str) -> Option<Entity> {
+    let (id, generation) = token.split_once(':')?;
+    Some(Entity::new(id.parse().ok()?, generation.parse().ok()?))
+}
+
+fn optional_entity_name(e: Option<Entity>) -> String {
+    e.map_or_else(|| "-".to_owned(), entity_name)
+}
+
+/// The tokens of one line, with the line number for error messages.
+struct Tokens<'a> {
+    line: usize,
+    tokens: std::str::SplitWhitespace<'a>,
+}
+
+impl<'a> Tokens<'a> {
+    fn error(&self, msg: &str) -> SnapshotError {
+        SnapshotError::Syntax(self.line, msg.to_owned())
+    }
+
+    fn next(&mut self, what: &str) -> Result<&'a str, SnapshotError> {
+        self.tokens
+            .next()
+            .ok_or_else(|| self.error(&format!("missing {}", what)))
+    }
+
+    fn parse<T: FromStr>(&mut self, what: &str) -> Result<T, SnapshotError> {
+        let token = self.next(what)?;
+        token
+            .parse()
+            .map_err(|_| self.error(&format!("invalid {} '{}'", what, token)))
+    }
+
+    fn with<T>(&mut self, what: &str, f: fn(&str) -> Option<T>) -> Result<T, SnapshotError> {
+        let token = self.next(what)?;
+        f(token).ok_or_else(|| self.error(&format!("invalid {} '{}'", what, token)))
+    }
+
+    fn entity(&mut self, what: &str) -> Result<Entity, SnapshotError> {
+        self.with(what, parse_entity)
+    }
+
+    fn optional_entity(&mut self, what: &str) -> Result<Option<Entity>, SnapshotError> {
+        match self.next(what)? {
+            "-" => Ok(None),
+            token => parse_entity(token)
+                .map(Some)
+                .ok_or_else(|| self.error(&format!("invalid {} '{}'", what, token))),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), SnapshotError> {
+        match self.next(keyword)? {
+            k if k == keyword => Ok(()),
+            k => Err(self.error(&format!("expected '{}', found '{}'", keyword, k))),
+        }
+    }
+
+    fn point(&mut self) -> Result<Point2, SnapshotError> {
+        Ok(Point2::new(self.parse("x")?, self.parse("y")?))
+    }
+
+    fn flag(&mut self, what: &str) -> Result<bool, SnapshotError> {
+        Ok(self.parse::<u8>(what)? != 0)
+    }
+
+    fn end(mut self) -> Result<(), SnapshotError> {
+        match self.tokens.next() {
+            None => Ok(()),
+            Some(token) => Err(self.error(&format!("unexpected '{}'", token))),
+        }
+    }
+}
+
+impl GameState {
+    /// Writes every entity with its stack, position, z-order, button and animation.
+    pub fn snapshot(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "solitaire-world {}", VERSION).unwrap();
+        writeln!(
+            out,
+            "world {} {} {} {} {}",
+            self.next_id, self.generation, self.busy as u8, self.dirty as u8, self.won as u8
+        )
+        .unwrap();
+        match self.drag_lock {
+            Some((src, drg)) => {
+                writeln!(out, "drag {} {}", entity_name(src), entity_name(drg)).unwrap()
+            }
+            None => writeln!(out, "drag -").unwrap(),
+        }
+
+        for e in self.iter() {
+            writeln!(out, "entity {}", entity_name(e)).unwrap();
+            if let Some(p) = self.get::<Point2>(e) {
+                writeln!(out, "position {} {}", p.x, p.y).unwrap();
+            }
+            if let Some(ZOrder(z)) = self.get::<ZOrder>(e) {
+                writeln!(out, "zorder {}", z).unwrap();
+            }
+            if let Some(s) = self.get::<Stack>(e) {
+                write!(out, "stack {}", role_name(s.role)).unwrap();
+                for &card in s.iter() {
+                    write!(out, " {}", card_name(card)).unwrap();
+                }
+                out.push('\n');
+            }
+            if let Some(b) = self.get::<Button>(e) {
+                write!(
+                    out,
+                    "button {} {}",
+                    color_name(b.color),
+                    button_state_name(b.state)
+                )
+                .unwrap();
+                match b.stacks {
+                    Some((t, s)) => {
+                        write!(out, " {}", entity_name(t)).unwrap();
+                        for e in s {
+                            write!(out, " {}", entity_name(e)).unwrap();
+                        }
+                    }
+                    None => out.push_str(" -"),
+                }
+                out.push('\n');
+            }
+            if let Some(a) = self.get::<Animation>(e) {
+                writeln!(
+                    out,
+                    "animation {} {} {} {} {} {} {}",
+                    a.target_pos.x,
+                    a.target_pos.y,
+                    optional_entity_name(a.target_stack),
+                    a.start_delay,
+                    a.time_left,
+                    sound_name(a.sound_start),
+                    sound_name(a.sound_stop)
+                )
+                .unwrap();
+            }
+        }
+        out
+    }
+
+    /// Rebuilds a world from a snapshot. Pending events are not part of a snapshot.
+    pub fn restore(text: &str) -> Result<GameState, SnapshotError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, l)| Tokens {
+                line: i + 1,
+                tokens: l.split_whitespace(),
+            })
+            .filter(|t| t.tokens.clone().next().is_some());
+
+        let mut header = lines
+            .next()
+            .ok_or(SnapshotError::Syntax(1, "empty snapshot".to_owned()))?;
+        if header.next("format")? != "solitaire-world" {
+            return Err(header.error("not a world snapshot"));
+        }
+        let version = header.next("version")?;
+        if version != VERSION.to_string() {
+            return Err(SnapshotError::Version(version.to_owned()));
+        }
+        let line = header.line;
+        header.end()?;
+
+        let mut state = GameState::default();
+        let mut world = lines
+            .next()
+            .ok_or(SnapshotError::Syntax(line + 1, "missing world".to_owned()))?;
+        world.keyword("world")?;
+        state.next_id = world.parse("next id")?;
+        state.generation = world.parse("generation")?;
+        state.busy = world.flag("busy")?;
+        state.dirty = world.flag("dirty")?;
+        state.won = world.flag("won")?;
+        let line = world.line;
+        world.end()?;
+
+        let mut drag = lines
+            .next()
+            .ok_or(SnapshotError::Syntax(line + 1, "missing drag".to_owned()))?;
+        drag.keyword("drag")?;
+        state.drag_lock = match drag.optional_entity("source")? {
+            Some(src) => Some((src, drag.entity("dragged entity")?)),
+            None => None,
+        };
+        drag.end()?;
+
+        let mut current = None;
+        for mut t in lines {
+            let keyword = t.next("keyword")?;
+            if keyword == "entity" {
+                let e = t.entity("entity")?;
+                if state.ent_lookup.contains_key(&e) {
+                    return Err(t.error("duplicate entity"));
+                }
+                let idx = state.components.push_row();
+                state.ent_lookup.insert(e, idx);
+                state.components.insert(idx, e);
+                current = Some(idx);
+                t.end()?;
+                continue;
+            }
+
+            let idx = current.ok_or_else(|| t.error("component before the first entity"))?;
+            match keyword {
+                "position" => {
+                    let p = t.point()?;
+                    state.components.insert(idx, p);
+                }
+                "zorder" => {
+                    let z = t.parse("z-order")?;
+                    state.components.insert(idx, ZOrder(z));
+                }
+                "stack" => {
+                    let mut stack = Stack::new(t.with("role", parse_role)?);
+                    while let Some(token) = t.tokens.next() {
+                        let card = parse_card(token)
+                            .ok_or_else(|| t.error(&format!("invalid card '{}'", token)))?;
+                        stack.push_card(card);
+                    }
+                    state.components.insert(idx, stack);
+                }
+                "button" => {
+                    let mut b = Button::new(t.with("color", parse_color)?);
+                    b.state = t.with("button state", parse_button_state)?;
+                    if let Some(target) = t.optional_entity("target stack")? {
+                        let mut sources = [target; 4];
+                        for s in sources.iter_mut() {
+                            *s = t.entity("source stack")?;
+                        }
+                        b.stacks = Some((target, sources));
+                    }
+                    state.components.insert(idx, b);
+                }
+                "animation" => {
+                    let a = Animation {
+                        target_pos: t.point()?,
+                        target_stack: t.optional_entity("target stack")?,
+                        start_delay: t.parse("start delay")?,
+                        time_left: t.parse("time left")?,
+                        sound_start: t.with("sound", parse_sound)?,
+                        sound_stop: t.with("sound", parse_sound)?,
+                    };
+                    state.components.insert(idx, a);
+                }
+                _ => return Err(t.error(&format!("unknown keyword '{}'", keyword))),
+            }
+            t.end()?;
+        }
+        state.check_references()?;
+        Ok(state)
+    }
+
+    /// Rejects worlds that refer to entities they do not have, that would give a new entity the
+    /// id of an existing one, or that animate something other than a card.
+    fn check_references(&self) -> Result<(), SnapshotError> {
+        let inconsistent = |msg: String| Err(SnapshotError::Inconsistent(msg));
+        if let Some(&e) = self.ent_lookup.keys().find(|e| e.id() >= self.next_id) {
+            return inconsistent(format!(
+                "entity {} is not below the next id {}",
+                entity_name(e),
+                self.next_id
+            ));
+        }
+        if let Some((src, dragged)) = self.drag_lock {
+            for e in [src, dragged] {
+                if self.get_stack(e).is_none() || self.get_position(e).is_none() {
+                    return inconsistent(format!("dragging without the stack {}", entity_name(e)));
+                }
+            }
+        }
+        let animated = self.components.query_ref::<(&Entity, &Animation)>();
+        for (&e, _) in animated {
+            if self.get_stack(e).is_none_or(|s| s.is_empty()) || self.get_position(e).is_none() {
+                return inconsistent(format!("animation without a card {}", entity_name(e)));
+            }
+        }
+        if let Some(&e) = self.dangling_references().first() {
+            return inconsistent(format!(
+                "reference to the missing entity {}",
+                entity_name(e)
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,320 @@
+//! Type keyed component storage.
+//!
+//! Every component type gets a column, a `Vec<Option<T>>` with one slot per entity, created the
+//! first time a component of that type is inserted. Rows are kept dense: removing an entity moves
+//! the last row into its place.
+//!
+//! Queries join columns by row. A query is a tuple of `&T` and `&mut T`, for example
+//! `components.query::<(&Point2, &mut Stack)>()` visits every entity that has both a position and
+//! a stack, with the stack writable. A component type may appear only once per query.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::iter;
+use std::marker::PhantomData;
+use std::slice;
+
+pub trait AnyColumn {
+    fn push_none(&mut self);
+    fn swap_remove(&mut self, idx: usize);
+    fn clear(&mut self);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static> AnyColumn for Vec<Option<T>> {
+    fn push_none(&mut self) {
+        self.push(None);
+    }
+
+    fn swap_remove(&mut self, idx: usize) {
+        Vec::swap_remove(self, idx);
+    }
+
+    fn clear(&mut self) {
+        Vec::clear(self);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[derive(Default)]
+pub struct Components {
+    columns: HashMap<TypeId, Box<dyn AnyColumn>>,
+    len: usize,
+}
+
+impl Components {
+    /// Appends a row without any components and returns its index.
+    pub fn push_row(&mut self) -> usize {
+        for c in self.columns.values_mut() {
+            c.push_none();
+        }
+        self.len += 1;
+        self.len - 1
+    }
+
+    /// Removes a row, the last row takes its place.
+    pub fn swap_remove_row(&mut self, idx: usize) {
+        for c in self.columns.values_mut() {
+            c.swap_remove(idx);
+        }
+        self.len -= 1;
+    }
+
+    pub fn clear(&mut self) {
+        for c in self.columns.values_mut() {
+            c.clear();
+        }
+        self.len = 0;
+    }
+
+    /// The column of a component type. Empty if no component of this type was ever inserted.
+    pub fn column<T: 'static>(&self) -> &[Option<T>] {
+        match self.columns.get(&TypeId::of::<T>()) {
+            Some(c) => c.as_any().downcast_ref::<Vec<Option<T>>>().unwrap(),
+            None => &[],
+        }
+    }
+
+    fn column_mut<T: 'static>(&mut self) -> &mut Vec<Option<T>> {
+        let len = self.len;
+        self.columns
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                Box::new(
+                    iter::repeat_with(|| None)
+                        .take(len)
+                        .collect::<Vec<Option<T>>>(),
+                )
+            })
+            .as_any_mut()
+            .downcast_mut()
+            .unwrap()
+    }
+
+    pub fn get<T: 'static>(&self, idx: usize) -> Option<&T> {
+        self.column().get(idx)?.as_ref()
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, idx: usize) -> Option<&mut T> {
+        self.columns
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut::<Vec<Option<T>>>()
+            .unwrap()
+            .get_mut(idx)?
+            .as_mut()
+    }
+
+    /// Sets the component of a row, returning the previous one.
+    pub fn insert<T: 'static>(&mut self, idx: usize, value: T) -> Option<T> {
+        assert!(idx < self.len, "row {} out of range", idx);
+        self.column_mut()[idx].replace(value)
+    }
+
+    pub fn take<T: 'static>(&mut self, idx: usize) -> Option<T> {
+        self.get_slot(idx)?.take()
+    }
+
+    fn get_slot<T: 'static>(&mut self, idx: usize) -> Option<&mut Option<T>> {
+        self.columns
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut::<Vec<Option<T>>>()
+            .unwrap()
+            .get_mut(idx)
+    }
+
+    pub fn query<'a, Q: Query<'a>>(&'a mut self) -> QueryIter<'a, Q> {
+        QueryIter {
+            columns: Q::columns(self),
+            marker: PhantomData,
+        }
+    }
+
+    /// Like `query`, for queries that only read.
+    pub fn query_ref<'a, Q: ReadQuery<'a>>(&'a self) -> QueryIter<'a, Q> {
+        QueryIter {
+            columns: Q::columns_ref(self),
+            marker: PhantomData,
+        }
+    }
+}
+
+/// One element of a query, `&T` or `&mut T`.
+pub trait Fetch<'a> {
+    type Item;
+    type Column: Iterator<Item = Option<Self::Item>>;
+
+    fn type_id() -> TypeId;
+    fn register(components: &mut Components);
+    fn column(column: &'a mut dyn AnyColumn) -> Self::Column;
+}
+
+/// A query element that only reads.
+pub trait ReadFetch<'a>: Fetch<'a> {
+    fn column_ref(column: Option<&'a dyn AnyColumn>) -> Self::Column;
+}
+
+type RefColumn<'a, T> = iter::Map<slice::Iter<'a, Option<T>>, fn(&'a Option<T>) -> Option<&'a T>>;
+type MutColumn<'a, T> =
+    iter::Map<slice::IterMut<'a, Option<T>>, fn(&'a mut Option<T>) -> Option<&'a mut T>>;
+
+impl<'a, T: 'static> Fetch<'a> for &'a T {
+    type Item = &'a T;
+    type Column = RefColumn<'a, T>;
+
+    fn type_id() -> TypeId {
+        TypeId::of::<T>()
+    }
+
+    fn register(components: &mut Components) {
+        components.column_mut::<T>();
+    }
+
+    fn column(column: &'a mut dyn AnyColumn) -> Self::Column {
+        Self::column_ref(Some(column))
+    }
+}
+
+impl<'a, T: 'static> ReadFetch<'a> for &'a T {
+    fn column_ref(column: Option<&'a dyn AnyColumn>) -> Self::Column {
+        let column: &'a [Option<T>] = match column {
+            Some(c) => c.as_any().downcast_ref::<Vec<Option<T>>>().unwrap(),
+            None => &[],
+        };
+        column.iter().map(Option::as_ref)
+    }
+}
+
+impl<'a, T: 'static> Fetch<'a> for &'a mut T {
+    type Item = &'a mut T;
+    type Column = MutColumn<'a, T>;
+
+    fn type_id() -> TypeId {
+        TypeId::of::<T>()
+    }
+
+    fn register(components: &mut Components) {
+        components.column_mut::<T>();
+    }
+
+    fn column(column: &'a mut dyn AnyColumn) -> Self::Column {
+        let column = column
+            .as_any_mut()
+            .downcast_mut::<Vec<Option<T>>>()
+            .unwrap();
+        column.iter_mut().map(Option::as_mut)
+    }
+}
+
+/// A tuple of query elements.
+pub trait Query<'a> {
+    type Item;
+    type Columns;
+
+    fn columns(components: &'a mut Components) -> Self::Columns;
+    fn next(columns: &mut Self::Columns) -> Option<Self::Item>;
+}
+
+/// A query that only reads.
+pub trait ReadQuery<'a>: Query<'a> {
+    fn columns_ref(components: &'a Components) -> Self::Columns;
+}
+
+pub struct QueryIter<'a, Q: Query<'a>> {
+    columns: Q::Columns,
+    marker: PhantomData<&'a Q>,
+}
+
+impl<'a, Q: Query<'a>> Iterator for QueryIter<'a, Q> {
+    type Item = Q::Item;
+
+    fn next(&mut self) -> Option<Q::Item> {
+        Q::next(&mut self.columns)
+    }
+}
+
+macro_rules! tuple_query {
+    ($($f:ident $c:ident),+) => {
+        impl<'a, $($f: Fetch<'a>),+> Query<'a> for ($($f,)+) {
+            type Item = ($(<$f as Fetch<'a>>::Item,)+);
+            type Columns = ($(<$f as Fetch<'a>>::Column,)+);
+
+            fn columns(components: &'a mut Components) -> Self::Columns {
+                $($f::register(components);)+
+                let [$($c),+] = components.columns.get_disjoint_mut([$(&$f::type_id()),+]);
+                ($($f::column(&mut **$c.unwrap()),)+)
+            }
+
+            fn next(columns: &mut Self::Columns) -> Option<Self::Item> {
+                let ($($c,)+) = columns;
+                loop {
+                    if let ($(Some($c),)+) = ($($c.next()?,)+) {
+                        return Some(($($c,)+));
+                    }
+                }
+            }
+        }
+
+        impl<'a, $($f: ReadFetch<'a>),+> ReadQuery<'a> for ($($f,)+) {
+            fn columns_ref(components: &'a Components) -> Self::Columns {
+                ($($f::column_ref(components.columns.get(&$f::type_id()).map(|c| &**c)),)+)
+            }
+        }
+    };
+}
+
+tuple_query!(A a);
+tuple_query!(A a, B b);
+tuple_query!(A a, B b, C c);
+tuple_query!(A a, B b, C c, D d);
+tuple_query!(A a, B b, C c, D d, E e);
+tuple_query!(A a, B b, C c, D d, E e, F f);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn join_columns() {
+        let mut c = Components::default();
+        for i in 0..4 {
+            let row = c.push_row();
+            c.insert(row, i as u32);
+            if i % 2 == 0 {
+                c.insert(row, i as f32);
+            }
+        }
+        c.insert(3, "three");
+
+        for (n, x) in c.query::<(&u32, &mut f32)>() {
+            *x += *n as f32;
+        }
+        let rows: Vec<_> = c.query_ref::<(&u32, &f32)>().collect();
+        assert_eq!(rows, [(&0, &0.0), (&2, &4.0)]);
+        assert_eq!(c.query_ref::<(&u32, &&str)>().count(), 1);
+        assert_eq!(c.query_ref::<(&u32, &u8)>().count(), 0);
+
+        c.swap_remove_row(0);
+        assert_eq!(c.column::<u32>().len(), 3);
+        assert_eq!(c.get::<&str>(0), Some(&"three"));
+        assert_eq!(c.take::<u32>(0), Some(3));
+        assert_eq!(c.get::<u32>(0), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn aliasing_query() {
+        let mut c = Components::default();
+        c.push_row();
+        c.query::<(&u32, &mut u32)>().count();
+    }
+}
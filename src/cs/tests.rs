@@ -0,0 +1,399 @@
+use solitaire_core::board::Board;
+use solitaire_core::deal::Deal;
+use solitaire_core::notation::card_name;
+use solitaire_core::rules::{self, Move};
+use solitaire_core::variations::ROOT;
+
+use crate::game::Game;
+use crate::recorder::Recorder;
+use crate::study::Study;
+use crate::types::*;
+
+use super::{GameEvent, GameState, SnapshotError, StaleEntity, entity_name};
+
+fn run(state: &mut GameState) {
+    for _ in 0..200 {
+        state.run_update(0.05);
+    }
+}
+
+fn dealt_game(seed: u64) -> (Game, Board) {
+    let deal = Deal::from_seed(seed);
+    let mut game = Game::new();
+    run(&mut game.state);
+    game.animate_deal(&deal);
+    run(&mut game.state);
+    game.state.drain_events();
+    (game, Board::new(&deal))
+}
+
+#[test]
+fn deal_and_automove() {
+    for seed in 0..5 {
+        let (game, board) = dealt_game(seed);
+        assert_eq!(game.export(), board.stacks());
+        assert!(!game.state.busy());
+    }
+}
+
+#[test]
+fn drag_and_drop() {
+    let (mut game, mut board) = dealt_game(1);
+    let m = board
+        .legal_moves()
+        .into_iter()
+        .find(|m| matches!(*m, Move::Cards(t, s, _) if t < 8 && s < 8))
+        .unwrap();
+    let Move::Cards(t, s, n) = m else {
+        unreachable!()
+    };
+
+    game.drag_cards(s, n, t);
+    run(&mut game.state);
+    board.apply(&m).unwrap();
+
+    assert_eq!(game.export(), board.stacks());
+    let src = game.stack_entity(s);
+    let dst = game.stack_entity(t);
+    let events: Vec<_> = game.state.drain_events().collect();
+    assert_eq!(
+        &events[..2],
+        &[
+            GameEvent::CardPickedUp { from: src, n },
+            GameEvent::CardsDropped {
+                from: src,
+                to: dst,
+                n
+            }
+        ]
+    );
+}
+
+#[test]
+fn rejected_drop() {
+    let (mut game, board) = dealt_game(2);
+    let stacks = board.stacks();
+    let (s, t) = (0..8)
+        .flat_map(|s| (0..8).map(move |t| (s, t)))
+        .find(|&(s, t)| s != t && !rules::is_valid_move(&stacks[t], stacks[s].top().unwrap(), 1))
+        .unwrap();
+
+    game.drag_cards(s, 1, t);
+    run(&mut game.state);
+
+    assert_eq!(game.export(), stacks);
+    let src = game.stack_entity(s);
+    let events: Vec<_> = game.state.drain_events().collect();
+    assert_eq!(
+        events,
+        [
+            GameEvent::CardPickedUp { from: src, n: 1 },
+            GameEvent::DropRejected { from: src, n: 1 }
+        ]
+    );
+}
+
+#[test]
+fn collapse_dragons() {
+    let (mut game, _) = dealt_game(3);
+    game.state.unchecked = true;
+    for i in 0..15 {
+        let e = game.stack_entity(i);
+        let stack = game.state.get_stack_mut(e).unwrap();
+        stack.cards.clear();
+        if i < 4 {
+            stack.push_card(Suite::Dragon(Color::Red));
+        }
+    }
+    game.state.dirty = true;
+    game.state.run_update(0.05);
+
+    game.click_button(Color::Red);
+    run(&mut game.state);
+
+    let stacks = game.export();
+    assert!(stacks[..8].iter().all(|s| s.is_empty()));
+    assert_eq!(stacks[8].cards, [Suite::FaceDown; 4]);
+    let events: Vec<_> = game.state.drain_events().collect();
+    assert_eq!(events[0], GameEvent::DragonsCollapsed(Color::Red));
+    assert_eq!(events[1], GameEvent::Sound(Sounds::Sweep));
+    assert!(!events.contains(&GameEvent::GameWon));
+}
+
+#[test]
+fn game_won_once() {
+    let (mut game, _) = dealt_game(4);
+    game.state.unchecked = true;
+    for i in 0..15 {
+        let e = game.stack_entity(i);
+        game.state.get_stack_mut(e).unwrap().cards.clear();
+    }
+    for (i, color) in [Color::Red, Color::Green, Color::White].iter().enumerate() {
+        let e = game.stack_entity(12 + i);
+        let stack = game.state.get_stack_mut(e).unwrap();
+        for rank in 1..9 {
+            stack.push_card(Suite::Number(rank, *color));
+        }
+        let e = game.stack_entity(i);
+        let stack = game.state.get_stack_mut(e).unwrap();
+        stack.push_card(Suite::Number(9, *color));
+    }
+    game.state.dirty = true;
+    run(&mut game.state);
+
+    let events: Vec<_> = game.state.drain_events().collect();
+    let moved = events
+        .iter()
+        .filter(|e| matches!(e, GameEvent::AutoMoved { .. }))
+        .count();
+    let won = events.iter().filter(|e| **e == GameEvent::GameWon).count();
+    assert_eq!((moved, won), (3, 1));
+    assert_eq!(events.last(), Some(&GameEvent::GameWon));
+
+    run(&mut game.state);
+    assert_eq!(game.state.drain_events().count(), 0);
+}
+
+#[test]
+fn study_records_and_restores() {
+    let (mut game, board) = dealt_game(1);
+    let mut study = Study::new(Deal::from_seed(1));
+    let moves: Vec<_> = board
+        .legal_moves()
+        .into_iter()
+        .filter(|m| matches!(*m, Move::Cards(t, s, _) if t < 8 && s < 8))
+        .take(2)
+        .collect();
+    assert_eq!(moves.len(), 2);
+
+    for &m in &moves {
+        study.show(ROOT, &mut game);
+        run(&mut game.state);
+        assert_eq!(game.export(), board.stacks());
+
+        let Move::Cards(t, s, n) = m else {
+            unreachable!()
+        };
+        game.drag_cards(s, n, t);
+        run(&mut game.state);
+        let events: Vec<_> = game.state.drain_events().collect();
+        for event in events {
+            assert!(study.record(&game, &event));
+        }
+        assert_eq!(game.export(), study.tree.board(study.node).stacks());
+    }
+    assert_eq!(study.tree.children(ROOT).len(), 2);
+    assert_eq!(study.tree.line(study.node), &moves[1..]);
+
+    study.switch(1, &mut game);
+    run(&mut game.state);
+    assert_eq!(study.tree.line(study.node), &moves[..1]);
+    assert_eq!(game.export(), study.tree.board(study.node).stacks());
+}
+
+#[test]
+fn record_and_replay() {
+    let (mut game, mut board) = dealt_game(2);
+    let mut recorder = Recorder::new(Some(2), Deal::from_seed(2));
+    for _ in 0..6 {
+        let Some(m) = board
+            .legal_moves()
+            .into_iter()
+            .find(|m| matches!(m, Move::Cards(t, s, _) if t != s && *t < 11))
+        else {
+            break;
+        };
+        let Move::Cards(t, s, n) = m else {
+            unreachable!()
+        };
+        game.drag_cards(s, n, t);
+        run(&mut game.state);
+        let events: Vec<_> = game.state.drain_events().collect();
+        for event in events {
+            assert!(recorder.record(&game, &event));
+        }
+        board.apply(&m).unwrap();
+    }
+    let replay = recorder.replay;
+    assert_eq!(replay.seed, Some(2));
+    assert!(!replay.moves.is_empty());
+    assert_eq!(replay.board(replay.moves.len()), board);
+
+    // flying the cards ends in the same positions as dragging them
+    let (mut game, _) = dealt_game(2);
+    for (i, (_, m)) in replay.moves.iter().enumerate() {
+        game.animate_move(m);
+        run(&mut game.state);
+        assert_eq!(game.export(), replay.board(i + 1).stacks());
+    }
+}
+
+#[test]
+fn stale_entities() {
+    let mut state = GameState::default();
+    let old = state
+        .new_entity()
+        .with_stack(Stack::new(StackRole::Sorting))
+        .build();
+    state.clear();
+    let new = state
+        .new_entity()
+        .with_stack(Stack::new(StackRole::Sorting))
+        .build();
+
+    assert_eq!(old.id(), new.id());
+    assert!(state.get_stack(old).is_none());
+    assert!(state.get_stack(new).is_some());
+    assert_eq!(state.remove_entity(old), Err(StaleEntity(old)));
+    assert_eq!(state.remove_entity(new), Ok(()));
+    assert!(state.get_position(new).is_none());
+}
+
+#[test]
+fn dangling_references() {
+    let mut game = Game::new();
+    let stack = game.stack_entity(0);
+    game.animate_giveup();
+    assert!(game.state.dangling_references().is_empty());
+
+    let mut button = Button::new(Color::Red);
+    button.stacks = Some((stack, [stack; 4]));
+    game.state.new_entity().with_button(button).build();
+    assert_eq!(game.state.dangling_references(), [stack; 5]);
+}
+
+#[test]
+fn snapshot_resumes_animations() {
+    let deal = Deal::from_seed(5);
+    let mut game = Game::new();
+    run(&mut game.state);
+    game.animate_deal(&deal);
+    for _ in 0..20 {
+        game.state.run_update(0.05);
+    }
+    assert!(game.state.busy());
+
+    let text = game.snapshot();
+    let mut restored = Game::restore(&text).unwrap();
+    assert_eq!(restored.snapshot(), text);
+
+    for _ in 0..200 {
+        game.state.run_update(0.05);
+        restored.state.run_update(0.05);
+        assert_eq!(restored.snapshot(), game.snapshot());
+    }
+    assert_eq!(restored.export(), Board::new(&deal).stacks());
+}
+
+#[test]
+fn invalid_snapshots() {
+    let text = GameState::default().snapshot();
+    assert!(GameState::restore(&text).is_ok());
+    assert_eq!(
+        GameState::restore("solitaire-world 2\n").err(),
+        Some(SnapshotError::Version("2".to_owned()))
+    );
+
+    let bad = format!("{}entity 0:0\nstack sorting 5r 10g\n", text);
+    assert_eq!(
+        GameState::restore(&bad).err(),
+        Some(SnapshotError::Syntax(5, "invalid card '10g'".to_owned()))
+    );
+    let bad = format!("{}position 1 2\n", text);
+    assert!(matches!(
+        GameState::restore(&bad),
+        Err(SnapshotError::Syntax(4, _))
+    ));
+    let inconsistent = |text: &str| {
+        matches!(
+            GameState::restore(text),
+            Err(SnapshotError::Inconsistent(_))
+        )
+    };
+    // entity 0 is not below the next id 0
+    assert!(inconsistent(&format!("{}entity 0:0\n", text)));
+
+    let mut game = Game::new();
+    let text = game.state.snapshot();
+    assert!(GameState::restore(&text).is_ok());
+    let stack = game.stack_entity(0);
+    let button = game
+        .state
+        .components
+        .query_ref::<(&Entity, &Button)>()
+        .next()
+        .map(|(&e, _)| e)
+        .unwrap();
+    let missing = Entity::new(999, 0);
+    for dragged in [missing, button] {
+        let drag = format!("drag {} {}", entity_name(stack), entity_name(dragged));
+        assert!(inconsistent(&text.replace("drag -", &drag)));
+    }
+    let mut dangling = Button::new(Color::Red);
+    dangling.stacks = Some((missing, [stack; 4]));
+    game.state.new_entity().with_button(dangling).build();
+    assert!(inconsistent(&game.state.snapshot()));
+
+    // animations of no card, with a target that exists
+    let animation = Animation {
+        start_delay: 0.0,
+        time_left: 1.0,
+        target_pos: Point2::origin(),
+        target_stack: Some(stack),
+        sound_start: Sounds::None,
+        sound_stop: Sounds::None,
+    };
+    let mut game = Game::new();
+    let text = game.state.snapshot();
+    game.state
+        .new_entity()
+        .with_animation(animation.clone())
+        .with_position(Point2::origin())
+        .build();
+    assert!(inconsistent(&game.state.snapshot()));
+    let mut game = GameState::restore(&text).unwrap();
+    game.new_entity()
+        .with_animation(animation)
+        .with_position(Point2::origin())
+        .with_stack(Stack::new(StackRole::Animation))
+        .build();
+    assert!(inconsistent(&game.snapshot()));
+}
+
+#[test]
+fn invariants() {
+    let (mut game, _) = dealt_game(6);
+    assert!(game.state.invariant_violations().is_empty());
+
+    let column = game.stack_entity(0);
+    let card = game
+        .state
+        .get_stack_mut(column)
+        .unwrap()
+        .pop_card()
+        .unwrap();
+    assert_eq!(
+        game.state.invariant_violations(),
+        [format!("0 copies of {} instead of 1", card_name(card))]
+    );
+
+    // a card on an empty foundation that is not a one
+    let foundation = game.stack_entity(12);
+    let stack = game.state.get_stack_mut(foundation).unwrap();
+    stack.cards.clear();
+    stack.push_card(Suite::Number(5, Color::Red));
+    let violations = game.state.invariant_violations();
+    assert!(violations.iter().any(|v| v.contains("ascending run")));
+
+    let restored = Game::restore(&game.snapshot()).unwrap();
+    assert_eq!(restored.state.invariant_violations(), violations);
+}
+
+#[test]
+#[should_panic(expected = "invariants violated")]
+fn lost_card_panics() {
+    let (mut game, _) = dealt_game(7);
+    let column = game.stack_entity(3);
+    game.state.get_stack_mut(column).unwrap().pop_card();
+    game.state.run_update(0.05);
+}
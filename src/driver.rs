@@ -0,0 +1,106 @@
+//! Plays deals, moves and collapses with the mouse code paths and checks the table against the
+//! rules once it is at rest. Shared by the console and the bot server.
+
+use solitaire_core::board::Board;
+use solitaire_core::deal::Deal;
+use solitaire_core::rules::{self, Move};
+
+use crate::game::Game;
+use crate::types::*;
+
+/// How a deal, move or collapse ended.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Dealt(Deal),
+    /// The table matches the rules, `legal` is false if the move was rejected.
+    Played {
+        legal: bool,
+    },
+    /// The table does not match the rules.
+    Mismatch(Vec<Stack>),
+}
+
+/// What the driver waits for while the game animates.
+#[derive(Default)]
+enum Step {
+    #[default]
+    Idle,
+    Shuffling(Deal),
+    Dealing(Deal),
+    Checking {
+        legal: bool,
+        expected: Board,
+    },
+}
+
+#[derive(Default)]
+pub struct Driver {
+    step: Step,
+}
+
+impl Driver {
+    /// True if nothing is in progress and the game is at rest.
+    pub fn ready(&self, game: &Game) -> bool {
+        matches!(self.step, Step::Idle) && game.state.idle()
+    }
+
+    /// Replaces the game with a new one and deals it once the deck is shuffled.
+    pub fn deal(&mut self, game: &mut Game, deal: Deal) {
+        *game = Game::new();
+        self.step = Step::Shuffling(deal);
+    }
+
+    /// Drags the top `n` cards of a stack onto another one. The stack must hold `n` cards.
+    pub fn move_cards(&mut self, game: &mut Game, from: usize, to: usize, n: usize) {
+        let board = Board::from_stacks(game.export());
+        self.play(&board, Move::Cards(to, from, n));
+        game.drag_cards(from, n, to);
+    }
+
+    /// Clicks the dragon button of a color.
+    pub fn collapse(&mut self, game: &mut Game, color: Color) {
+        let board = Board::from_stacks(game.export());
+        match rules::check_button(color, board.stacks().iter()) {
+            Some((t, s)) => self.play(&board, Move::Button(color, t, s)),
+            None => {
+                self.step = Step::Checking {
+                    legal: false,
+                    expected: board,
+                }
+            }
+        }
+        game.click_button(color);
+    }
+
+    /// Remembers what the table should look like after a move.
+    fn play(&mut self, board: &Board, m: Move) {
+        let mut expected = board.clone();
+        let legal = expected.apply(&m).is_ok();
+        self.step = Step::Checking { legal, expected };
+    }
+
+    /// Advances once the game is at rest, returns the outcome when the current action is done.
+    pub fn update(&mut self, game: &mut Game) -> Option<Outcome> {
+        if !game.state.idle() {
+            return None;
+        }
+
+        match std::mem::take(&mut self.step) {
+            Step::Idle => None,
+            Step::Shuffling(deal) => {
+                game.animate_deal(&deal);
+                self.step = Step::Dealing(deal);
+                None
+            }
+            Step::Dealing(deal) => Some(Outcome::Dealt(deal)),
+            Step::Checking { legal, expected } => {
+                let table = game.export();
+                if table == expected.stacks() {
+                    Some(Outcome::Played { legal })
+                } else {
+                    Some(Outcome::Mismatch(table))
+                }
+            }
+        }
+    }
+}
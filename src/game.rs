@@ -1,7 +1,8 @@
-use rand::{rng, seq::SliceRandom};
-
-use crate::cs::GameState;
+use crate::cs::{GameEvent, GameState, SnapshotError, entity_name, parse_entity};
+use crate::deal::Deal;
+use crate::rules::{self, Move};
 use crate::types::*;
+use solitaire_core::board::Board;
 
 pub struct Game {
     pub state: GameState,
@@ -11,6 +12,8 @@ pub struct Game {
     game_stacks: Vec<Entity>,
     target_stacks: [Entity; 3],
     //dragon_stacks: [Entity; 3],
+    // the deal on the table, unknown after restoring a snapshot
+    deal: Option<Deal>,
 }
 
 impl Game {
@@ -116,6 +119,7 @@ impl Game {
             game_stacks: vec![a, b, c, d, e, f, g, h],
             target_stacks: [x, y, z],
             //dragon_stacks: [r, s, t],
+            deal: None,
         };
 
         game.animate_shuffle();
@@ -123,6 +127,148 @@ impl Game {
         game
     }
 
+    /// The entity of a stack, numbered as in `solitaire_core::board`.
+    pub fn stack_entity(&self, idx: usize) -> Entity {
+        self.all_stacks[idx]
+    }
+
+    /// The board index of a stack entity, `None` for other entities.
+    pub fn stack_index(&self, e: Entity) -> Option<usize> {
+        self.all_stacks.iter().position(|&s| s == e)
+    }
+
+    /// Drags the top `n` cards of a stack onto another one with the mouse, as a player would.
+    pub fn drag_cards(&mut self, from: usize, n: usize, to: usize) {
+        let src = self.all_stacks[from];
+        let dst = self.all_stacks[to];
+        let len = self.state.get_stack(src).map_or(0, |s| s.len());
+        let len_dst = self.state.get_stack(dst).map_or(0, |s| s.len());
+        let (Some(start), Some(target)) = (
+            self.state.card_position(src, len.saturating_sub(n)),
+            self.state.card_position(dst, len_dst),
+        ) else {
+            return;
+        };
+
+        let grip = Vector2::new(10.0, 10.0);
+        let rel = target - start;
+        self.state
+            .handle_mouse_button_down(start.x + grip.x, start.y + grip.y);
+        self.state.handle_mouse_move(rel.x, rel.y);
+        self.state
+            .handle_mouse_button_up(target.x + grip.x, target.y + grip.y);
+    }
+
+    /// Clicks the dragon button of a color.
+    pub fn click_button(&mut self, color: Color) {
+        if let Some(p) = self.state.button_position(color) {
+            self.state.handle_mouse_button_down(p.x, p.y);
+            self.state.handle_mouse_button_up(p.x, p.y);
+        }
+    }
+
+    /// The move a player event made on `board`, the position before it. `None` for events that
+    /// are not moves, and for a collapse the board does not allow.
+    pub fn event_move(&self, event: &GameEvent, board: &Board) -> Option<Move> {
+        match *event {
+            GameEvent::CardsDropped { from, to, n } if from != to => Some(Move::Cards(
+                self.stack_index(to)?,
+                self.stack_index(from)?,
+                n,
+            )),
+            GameEvent::DragonsCollapsed(color) => {
+                let (t, s) = rules::check_button(color, board.stacks().iter())?;
+                Some(Move::Button(color, t, s))
+            }
+            _ => None,
+        }
+    }
+
+    /// Plays a move by flying its cards to the target instead of dragging them, for replays.
+    pub fn animate_move(&mut self, m: &Move) {
+        let Move::Cards(t, s, n) = *m else {
+            if let Move::Button(color, _, _) = *m {
+                self.click_button(color);
+            }
+            return;
+        };
+        let src = self.all_stacks[s];
+        let dst = self.all_stacks[t];
+        let stack = self.state.get_stack_mut(src).unwrap();
+        let from = stack.len() - n;
+        let cards = stack.split(from);
+        let to = self.state.get_stack(dst).unwrap().len();
+        for (i, &card) in cards.iter().enumerate() {
+            let ani = Animation {
+                target_pos: self.state.card_position(dst, to + i).unwrap(),
+                target_stack: Some(dst),
+                // the cards land in order
+                start_delay: 0.05 * i as f32,
+                time_left: 0.2,
+                sound_start: if i == 0 { Sounds::Pickup } else { Sounds::None },
+                sound_stop: if i == n - 1 {
+                    Sounds::Place
+                } else {
+                    Sounds::None
+                },
+            };
+            let pos = self.state.card_position(src, from + i).unwrap();
+            self.state.animate(card, pos, 100.0 + i as f32, ani);
+        }
+    }
+
+    /// A snapshot of the world, preceded by a `game` line with the stacks in board order.
+    pub fn snapshot(&self) -> String {
+        let stacks: Vec<_> = self.all_stacks.iter().map(|&e| entity_name(e)).collect();
+        format!("game {}\n{}", stacks.join(" "), self.state.snapshot())
+    }
+
+    pub fn restore(text: &str) -> Result<Game, SnapshotError> {
+        let (first, world) = text.split_once('\n').unwrap_or((text, ""));
+        let stacks = match first.strip_prefix("game ") {
+            Some(stacks) => stacks
+                .split_whitespace()
+                .map(parse_entity)
+                .collect::<Option<Vec<_>>>(),
+            None => None,
+        };
+        let all_stacks = match stacks {
+            Some(stacks) if stacks.len() == 15 => stacks,
+            _ => return Err(SnapshotError::Syntax(1, "invalid game line".to_owned())),
+        };
+
+        let state = GameState::restore(world).map_err(|e| match e {
+            SnapshotError::Syntax(line, msg) => SnapshotError::Syntax(line + 1, msg),
+            e => e,
+        })?;
+        if let Some(&e) = all_stacks.iter().find(|&&e| state.get_stack(e).is_none()) {
+            return Err(SnapshotError::Syntax(
+                1,
+                format!("{} is not a stack", entity_name(e)),
+            ));
+        }
+
+        Ok(Game {
+            state,
+            flower_stack: all_stacks[11],
+            game_stacks: all_stacks[..8].to_vec(),
+            target_stacks: [all_stacks[12], all_stacks[13], all_stacks[14]],
+            all_stacks,
+            deal: None,
+        })
+    }
+
+    pub fn deal(&self) -> Option<&Deal> {
+        self.deal.as_ref()
+    }
+
+    /// Shows a position of a deal at once, in board order.
+    pub fn set_position(&mut self, deal: &Deal, stacks: &[Stack]) {
+        self.deal = Some(deal.clone());
+        let stacks = self.all_stacks.iter().copied().zip(stacks.iter().cloned());
+        self.state.set_stacks(stacks);
+    }
+
     pub fn export(&self) -> Vec<Stack> {
         self.all_stacks
             .iter()
@@ -133,38 +279,13 @@ impl Game {
     pub fn check_win_condition(&self) -> bool {
         self.game_stacks
             .iter()
-            .all(|&s| self.state.get_stack(s).unwrap().len() == 0)
+            .all(|&s| self.state.get_stack(s).unwrap().is_empty())
             && self
                 .target_stacks
                 .iter()
                 .all(|&s| self.state.get_stack(s).unwrap().len() == 9)
     }
 
-    pub fn shuffled_deck() -> Stack {
-        let mut cards = Vec::with_capacity(40);
-
-        for _ in 0..4 {
-            cards.push(Suite::Dragon(Color::Red));
-            cards.push(Suite::Dragon(Color::Green));
-            cards.push(Suite::Dragon(Color::White));
-        }
-
-        for i in 1..10 {
-            cards.push(Suite::Number(i, Color::Red));
-            cards.push(Suite::Number(i, Color::Green));
-            cards.push(Suite::Number(i, Color::White));
-        }
-
-        cards.push(Suite::Flower);
-
-        cards.shuffle(&mut rng());
-
-        Stack {
-            cards,
-            role: StackRole::Generic,
-        }
-    }
-
     pub fn animate_shuffle(&mut self) {
         let f = self.flower_stack;
         let target_stack = Some(f);
@@ -192,15 +313,14 @@ impl Game {
         }
     }
 
-    pub fn animate_deal(&mut self) {
+    pub fn animate_deal(&mut self, deal: &Deal) {
+        self.deal = Some(deal.clone());
         self.state
             .get_stack_mut(self.flower_stack)
             .unwrap()
             .cards
             .clear();
 
-        let mut new_deck = Game::shuffled_deck(); // TODO: should this be a parameter to the function?
-
         let fpos = *self.state.get_position(self.flower_stack).unwrap();
         let fshift = self
             .state
@@ -210,9 +330,9 @@ impl Game {
 
         let mut height = 0.0;
         let mut s = 0;
-        let mut z = new_deck.len() as f32;
+        let mut z = deal.cards().len() as f32;
         let mut start_delay = 0.0;
-        while let Some(card) = new_deck.pop_card() {
+        for &card in deal.cards() {
             let target_stack = self.game_stacks[s];
             let shift = self.state.get_stack(target_stack).unwrap().get_stackshift();
             let target_pos = *self.state.get_position(target_stack).unwrap() + shift * height;
@@ -243,7 +363,7 @@ impl Game {
     pub fn animate_giveup(&mut self) {
         let mut cards = Vec::with_capacity(40);
 
-        for &e in self.state.iter() {
+        for e in self.state.iter() {
             let pos = match self.state.get_position(e) {
                 Some(p) => p,
                 None => continue,
@@ -265,7 +385,7 @@ impl Game {
         for (z, (card, start_pos)) in cards.into_iter().enumerate() {
             let mut direction = start_pos - Point2::new(640.0, 400.0);
             let dist = direction.norm();
-            direction = direction / dist;
+            direction /= dist;
 
             let target_pos = start_pos + direction * 800.0;
 
@@ -15,7 +15,10 @@ pub struct GiveupState {
 impl EventHandler for GiveupState {
     fn update(&mut self, ctx: &mut Context) -> GameResult {
         let dt = ctx.time.delta().as_secs_f32();
-        self.game.state.run_update(dt, &mut self.resources);
+        self.game.state.run_update(dt);
+        for event in self.game.state.drain_events() {
+            self.resources.play_event_sound(&event);
+        }
 
         if !self.game.state.busy() {
             ctx.request_quit();
@@ -1,20 +1,58 @@
+use crate::analysis::Analysis;
+use crate::bot::BotServer;
+use crate::console::Console;
+use crate::cs::{DebugInfo, GameEvent};
 use crate::game::Game;
+use crate::recorder::Recorder;
 use crate::resources::Resources;
+use crate::study::Study;
 use ggez::event::EventHandler;
 use ggez::graphics::Canvas;
 use ggez::input::keyboard::{KeyCode, KeyInput};
 use ggez::input::mouse::MouseButton;
 use ggez::winit::keyboard::PhysicalKey;
-use ggez::{Context, GameResult};
+use ggez::{Context, GameError, GameResult};
+use log::warn;
+use std::io::{Read, Write};
+use std::time::Duration;
 
-use crate::ai::{AiResult, AiState};
+use crate::ai::{AiResult, AiState, Budget, DEFAULT_TABLE_BYTES, Weights};
+use crate::deal::Deal;
+use solitaire_core::board::Board;
+use solitaire_core::replay::Outcome;
+use solitaire_core::variations::ROOT;
 
 use super::welcome_state::WelcomeState;
 
+/// The solver runs in the frame of the key press, so it must finish quickly.
+const SOLVER_BUDGET: Budget = Budget {
+    nodes: 10000,
+    time: Some(Duration::from_millis(500)),
+    memory: Some(256 << 20),
+    table_bytes: DEFAULT_TABLE_BYTES,
+    threads: 1,
+    weights: Weights::DEFAULT,
+    cancel: None,
+};
+
 pub struct MainState {
     pub resources: Resources,
     pub game: Game,
-    win_counted: bool,
+    pub console: Option<Console>,
+    pub bot: Option<BotServer>,
+    // leave the game instead of going back to the welcome screen
+    pub exit: bool,
+
+    // the seed of the first deal, for its replay
+    seed: Option<u64>,
+    recorder: Option<Recorder>,
+
+    debug_overlay: bool,
+    analysis: Option<Analysis>,
+    study: Option<Study>,
+    // the comment being typed for the current node of the study
+    comment: Option<String>,
+    verdict: Option<String>,
 
     last_x: f32,
     last_y: f32,
@@ -28,13 +66,70 @@ impl EventHandler for MainState {
         }
 
         let dt = ctx.time.delta().as_secs_f32();
-        self.game.state.run_update(dt, &mut self.resources);
+        self.game.state.run_update(dt);
+        let events: Vec<_> = self.game.state.drain_events().collect();
+        if self.study.as_ref().map(|s| s.tree.deal()) != self.game.deal() {
+            self.study = None;
+        }
+        if self.recorder.as_ref().map(|r| &r.replay.deal) != self.game.deal() {
+            self.stop_recording(ctx)?;
+        }
+        for event in events {
+            if let Some(recorder) = &mut self.recorder
+                && !recorder.record(&self.game, &event)
+            {
+                warn!("the table left the recorded game, recording stopped");
+                self.stop_recording(ctx)?;
+            }
+            if let Some(study) = &mut self.study
+                && !study.record(&self.game, &event)
+            {
+                warn!("the table left the variation tree, recording stopped");
+                self.study = None;
+            }
+            self.resources.play_event_sound(&event);
+            if let Some(bot) = &mut self.bot {
+                bot.publish(&self.game, &event);
+            }
+            if event == GameEvent::GameWon {
+                self.end_game(ctx, Outcome::Won)?;
+                if self.console.is_some() {
+                    println!("won");
+                } else if self.bot.is_none() {
+                    ctx.request_quit();
+                }
+            }
+        }
+
+        if let Some(bot) = &mut self.bot {
+            bot.update(&mut self.game);
+        }
+
+        // trees and replays start at the dealt position, scripted and bot games are not recorded
+        if (self.study.is_none() || (self.recorder.is_none() && self.keeps_stats()))
+            && self.game.state.idle()
+            && let Some(deal) = self.game.deal()
+            && Board::new(deal).stacks() == self.game.export()
+        {
+            if self.study.is_none() {
+                self.study = Some(Study::new(deal.clone()));
+            }
+            if self.recorder.is_none() {
+                self.recorder = Some(Recorder::new(self.seed, deal.clone()));
+            }
+        }
 
-        if self.game.check_win_condition() {
-            if !self.win_counted {
-                self.resources.add_win(ctx);
-                self.win_counted = true;
+        if let Some(analysis) = &mut self.analysis {
+            if !self.game.state.busy() && !analysis.is_current(&self.game) {
+                analysis.restart(&self.game);
             }
+            analysis.update();
+        }
+
+        if let Some(console) = &mut self.console
+            && !console.update(&mut self.game)
+        {
+            self.exit = true;
             ctx.request_quit();
         }
 
@@ -46,6 +141,27 @@ impl EventHandler for MainState {
         self.game
             .state
             .run_render(ctx, &mut self.resources, &mut canvas)?;
+        if self.debug_overlay {
+            let info = DebugInfo {
+                fps: ctx.time.fps(),
+                verdict: self.verdict.clone(),
+            };
+            self.game
+                .state
+                .debug_render_system(ctx, &mut canvas, &info)?;
+        }
+        if let Some(analysis) = &self.analysis
+            && analysis.is_current(&self.game)
+        {
+            let moves = analysis.view(&self.game);
+            let mut study = self.study.as_ref().map_or(Vec::new(), |s| s.summary());
+            if let Some(comment) = &self.comment {
+                study.push(format!("comment: {}_", comment));
+            }
+            self.game
+                .state
+                .analysis_render_system(ctx, &mut canvas, &moves, &study)?;
+        }
         canvas.finish(&mut ctx.gfx)?;
         Ok(())
     }
@@ -57,9 +173,7 @@ impl EventHandler for MainState {
         x: f32,
         y: f32,
     ) -> GameResult {
-        self.game
-            .state
-            .handle_mouse_button_down(x, y, &mut self.resources);
+        self.game.state.handle_mouse_button_down(x, y);
         Ok(())
     }
 
@@ -70,9 +184,7 @@ impl EventHandler for MainState {
         x: f32,
         y: f32,
     ) -> GameResult {
-        self.game
-            .state
-            .handle_mouse_button_up(x, y, &mut self.resources);
+        self.game.state.handle_mouse_button_up(x, y);
         Ok(())
     }
 
@@ -98,15 +210,81 @@ impl EventHandler for MainState {
         input: KeyInput,
         _repeated: bool,
     ) -> GameResult {
+        if self.comment.is_some() {
+            self.edit_comment(input);
+            return Ok(());
+        }
+        if self.analysis.is_some()
+            && let Some(study) = &self.study
+            && input.event.physical_key == PhysicalKey::Code(KeyCode::Enter)
+        {
+            self.comment = Some(study.tree.comment(study.node).to_owned());
+            return Ok(());
+        }
+        if self.analysis.is_some() && self.navigate(input.event.physical_key) {
+            // the table no longer follows the game
+            self.stop_recording(ctx)?;
+            return Ok(());
+        }
         match input.event.physical_key {
             PhysicalKey::Code(KeyCode::Escape) => ctx.request_quit(),
+            PhysicalKey::Code(KeyCode::F3) => self.debug_overlay = !self.debug_overlay,
+            PhysicalKey::Code(KeyCode::F4) => {
+                self.analysis = match self.analysis {
+                    Some(_) => None,
+                    None => Some(Analysis::start(&self.game)),
+                }
+            }
+            PhysicalKey::Code(KeyCode::F5) => {
+                let mut f = ctx.fs.create("/savegame.txt")?;
+                f.write_all(self.game.snapshot().as_bytes())?;
+            }
+            PhysicalKey::Code(KeyCode::F7) => match &self.study {
+                Some(study) => {
+                    let mut f = ctx.fs.create("/variations.txt")?;
+                    f.write_all(study.tree.to_string().as_bytes())?;
+                }
+                None => println!("no variation tree"),
+            },
+            PhysicalKey::Code(KeyCode::F8) if self.game.state.idle() => {
+                match ctx.fs.open("/variations.txt") {
+                    Ok(mut f) => {
+                        let mut text = String::new();
+                        f.read_to_string(&mut text)?;
+                        match Study::load(&text, &mut self.game) {
+                            Ok(study) => {
+                                self.study = Some(study);
+                                self.stop_recording(ctx)?;
+                            }
+                            Err(e) => println!("variations.txt: {}", e),
+                        }
+                    }
+                    Err(GameError::ResourceNotFound(_, _)) => println!("no saved variations"),
+                    Err(e) => return Err(e),
+                }
+            }
+            PhysicalKey::Code(KeyCode::F9) => match ctx.fs.open("/savegame.txt") {
+                Ok(mut f) => {
+                    let mut text = String::new();
+                    f.read_to_string(&mut text)?;
+                    match Game::restore(&text) {
+                        Ok(game) => self.game = game,
+                        Err(e) => println!("savegame.txt: {}", e),
+                    }
+                }
+                Err(GameError::ResourceNotFound(_, _)) => println!("no saved game"),
+                Err(e) => return Err(e),
+            },
             PhysicalKey::Code(KeyCode::Backspace) => {
                 let ai = AiState::new(self.game.export());
-                match ai.astar(10000) {
-                    AiResult::Unknown => println!("?"),
-                    AiResult::Winable(n) => println!("{} :-)", n),
-                    AiResult::Lost => println!(":-("),
-                }
+                let (result, stats) = ai.astar_with_stats(&SOLVER_BUDGET);
+                let verdict = match result {
+                    AiResult::Unknown => "unknown".to_owned(),
+                    AiResult::Winable(path) => format!("winnable in {} moves", path.len()),
+                    AiResult::Lost => "lost".to_owned(),
+                };
+                println!("{}: {}", verdict, stats);
+                self.verdict = Some(verdict);
             }
             _ => {}
         }
@@ -114,13 +292,115 @@ impl EventHandler for MainState {
     }
 }
 
+impl MainState {
+    /// Statistics and replays are kept of the games of the player only.
+    fn keeps_stats(&self) -> bool {
+        self.console.is_none() && self.bot.is_none()
+    }
+
+    /// Saves the replay of a game that goes on without it. It stays in the history of the
+    /// statistics, but is not counted.
+    fn stop_recording(&mut self, ctx: &mut Context) -> GameResult {
+        if let Some(recorder) = self.recorder.take()
+            && let Some(game) = recorder.save(ctx, Outcome::Unfinished)?
+        {
+            self.resources.record_game(ctx, game);
+        }
+        Ok(())
+    }
+
+    /// Counts the end of a game in the statistics, with its replay if it was recorded to the end.
+    fn end_game(&mut self, ctx: &mut Context, outcome: Outcome) -> GameResult {
+        if !self.keeps_stats() {
+            return Ok(());
+        }
+        match self.recorder.take() {
+            Some(recorder) => {
+                // without a single move it was no game
+                if let Some(game) = recorder.save(ctx, outcome)? {
+                    self.resources.record_game(ctx, game);
+                }
+            }
+            None => self.resources.count_game(ctx, outcome),
+        }
+        Ok(())
+    }
+
+    /// Ends the game when it is left: a game that is not won was given up, unless the whole
+    /// program quits. A won game was counted when it was won.
+    pub fn finish(&mut self, ctx: &mut Context) -> GameResult {
+        if self.exit {
+            self.stop_recording(ctx)
+        } else if self.game.check_win_condition() {
+            Ok(())
+        } else {
+            self.end_game(ctx, Outcome::GaveUp)
+        }
+    }
+
+    /// Moves through the variation tree, true if the key was for that.
+    fn navigate(&mut self, key: PhysicalKey) -> bool {
+        let Some(study) = &mut self.study else {
+            return false;
+        };
+        if !self.game.state.idle() {
+            return false;
+        }
+        let game = &mut self.game;
+        match key {
+            PhysicalKey::Code(KeyCode::ArrowLeft) => study.back(game),
+            PhysicalKey::Code(KeyCode::ArrowRight) => study.forward(game),
+            PhysicalKey::Code(KeyCode::ArrowUp) => study.switch(-1, game),
+            PhysicalKey::Code(KeyCode::ArrowDown) => study.switch(1, game),
+            PhysicalKey::Code(KeyCode::Home) => study.show(ROOT, game),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Types the comment of the current node, Enter keeps it and Escape drops it.
+    fn edit_comment(&mut self, input: KeyInput) {
+        let comment = self.comment.as_mut().unwrap();
+        match input.event.physical_key {
+            PhysicalKey::Code(KeyCode::Enter) => {
+                if let Some(study) = &mut self.study {
+                    study.tree.annotate(study.node, comment);
+                }
+                self.comment = None;
+            }
+            PhysicalKey::Code(KeyCode::Escape) => self.comment = None,
+            PhysicalKey::Code(KeyCode::Backspace) => {
+                comment.pop();
+            }
+            _ => {
+                if let Some(text) = &input.event.text {
+                    comment.extend(text.chars().filter(|c| !c.is_control()));
+                }
+            }
+        }
+    }
+}
+
 impl From<WelcomeState> for MainState {
     fn from(mut old: WelcomeState) -> MainState {
-        old.game.animate_deal();
+        let (seed, deal) = old.deal.take().unwrap_or_else(|| {
+            let seed = Deal::random_seed();
+            (Some(seed), Deal::from_seed(seed))
+        });
+        old.game.animate_deal(&deal);
         MainState {
             resources: old.resources,
             game: old.game,
-            win_counted: false,
+            console: None,
+            bot: None,
+            exit: false,
+            seed,
+            recorder: None,
+            debug_overlay: false,
+            analysis: None,
+            study: None,
+            comment: None,
+            verdict: None,
             last_x: 0.0,
             last_y: 0.0,
         }
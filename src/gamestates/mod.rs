@@ -1,5 +1,7 @@
 mod giveup_state;
 mod main_state;
+mod replay_state;
+mod stats_state;
 mod victory_state;
 mod welcome_state;
 
@@ -7,18 +9,28 @@ use ggez::{Context, GameError, GameResult};
 use std::fmt;
 use std::fmt::Formatter;
 
+use crate::bot::BotServer;
+use crate::console::Console;
+
 use self::giveup_state::GiveupState;
 use self::main_state::MainState;
+use self::replay_state::ReplayState;
+use self::stats_state::StatsState;
 use self::victory_state::VictoryState;
 use self::welcome_state::WelcomeState;
 
 pub struct GameWrapper {
     state: GameState,
+    // handed to each game, kept here in between
+    console: Option<Console>,
+    bot: Option<BotServer>,
 }
 #[derive(Default)]
 enum GameState {
     Welcome(WelcomeState),
-    Game(MainState),
+    Game(Box<MainState>),
+    Replay(Box<ReplayState>),
+    Stats(Box<StatsState>),
     Victory(VictoryState),
     GiveUp(GiveupState),
     #[default]
@@ -32,16 +44,26 @@ use ggez::input::mouse::MouseButton;
 use log::info;
 
 impl GameWrapper {
-    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+    pub fn new(
+        ctx: &mut Context,
+        console: Option<Console>,
+        bot: Option<BotServer>,
+    ) -> GameResult<Self> {
         let state = Welcome(WelcomeState::new(ctx)?);
         info!("Entering game state {}", state);
-        Ok(GameWrapper { state })
+        Ok(GameWrapper {
+            state,
+            console,
+            bot,
+        })
     }
 
     fn handler(&mut self) -> &mut dyn EventHandler {
         match &mut self.state {
             Welcome(s) => s,
-            Game(s) => s,
+            Game(s) => s.as_mut(),
+            Replay(s) => s.as_mut(),
+            Stats(s) => s.as_mut(),
             Victory(s) => s,
             GiveUp(s) => s,
             Quit => panic!("Invalid Game State"),
@@ -52,15 +74,26 @@ impl GameWrapper {
         info!("Leaving game state {}", self.state);
         match std::mem::take(&mut self.state) {
             Welcome(state) => {
-                if state.move_on {
-                    self.state = Game(state.into());
+                if state.move_on && state.stats {
+                    self.state = Stats(Box::new(state.into()));
+                } else if state.move_on && state.replay.is_some() {
+                    self.state = Replay(Box::new(state.into()));
+                } else if state.move_on {
+                    let mut state: MainState = state.into();
+                    state.console = self.console.take();
+                    state.bot = self.bot.take();
+                    self.state = Game(Box::new(state));
                 }
             }
-            Game(state) => {
-                if state.game.check_win_condition() {
-                    self.state = Victory(state.into());
+            Game(mut state) => {
+                self.console = state.console.take();
+                self.bot = state.bot.take();
+                if state.exit {
+                    // stay in Quit
+                } else if state.game.check_win_condition() {
+                    self.state = Victory((*state).into());
                 } else {
-                    self.state = GiveUp(state.into());
+                    self.state = GiveUp((*state).into());
                 }
             }
             Victory(state) => {
@@ -73,9 +106,21 @@ impl GameWrapper {
             GiveUp(state) => {
                 self.state = Welcome(state.into());
             }
+            Replay(state) => {
+                self.state = Welcome((*state).into());
+            }
+            Stats(state) => {
+                self.state = Welcome((*state).into());
+            }
             Quit => {}
         }
         info!("Entering game state {}", self.state);
+        // a game picked on the statistics screen starts right away
+        if let Welcome(state) = &self.state
+            && state.move_on
+        {
+            self.advance();
+        }
     }
 }
 
@@ -84,6 +129,16 @@ impl EventHandler for GameWrapper {
         if matches!(self.state, Quit) {
             return Ok(());
         }
+        // scripted and bot games start right away
+        if let Welcome(s) = &mut self.state
+            && (self.console.is_some() || self.bot.is_some())
+            && s.ready
+            && !s.move_on
+            && !s.game.state.busy()
+        {
+            s.move_on = true;
+            ctx.request_quit();
+        }
         self.handler().update(ctx)
     }
 
@@ -129,7 +184,10 @@ impl EventHandler for GameWrapper {
         self.handler().key_down_event(ctx, input, repeated)
     }
 
-    fn quit_event(&mut self, _ctx: &mut Context) -> Result<bool, GameError> {
+    fn quit_event(&mut self, ctx: &mut Context) -> Result<bool, GameError> {
+        if let Game(state) = &mut self.state {
+            state.finish(ctx)?;
+        }
         self.advance();
         Ok(!matches!(self.state, Quit))
     }
@@ -140,6 +198,8 @@ impl fmt::Display for GameState {
         let name = match *self {
             Welcome(_) => "Welcome",
             Game(_) => "Game",
+            Replay(_) => "Replay",
+            Stats(_) => "Stats",
             Victory(_) => "Victory",
             GiveUp(_) => "GiveUp",
             Quit => "Quit",
@@ -0,0 +1,202 @@
+use std::time::Duration;
+
+use ggez::event::*;
+use ggez::graphics::{self, Canvas, DrawMode, DrawParam, Drawable, Mesh, PxScale, Rect, Text};
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::mint::Point2;
+use ggez::winit::keyboard::PhysicalKey;
+use ggez::{Context, GameResult};
+
+use solitaire_core::replay::{Replay, VerifyError};
+
+use crate::game::Game;
+use crate::resources::Resources;
+
+use super::welcome_state::WelcomeState;
+
+/// Longer pauses of the player are shortened to this, so watching a replay is not boring.
+const MAX_PAUSE: Duration = Duration::from_secs(3);
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 16.0;
+/// Moves skipped by Page Up and Page Down.
+const SEEK_STEP: usize = 10;
+
+const HELP: &str =
+    "Space play/pause  Left/Right step  Up/Down speed  Home/End/PgUp/PgDn seek  Esc leave";
+
+/// Plays a recorded game back on the table, with the moves animated like the automatic ones.
+pub struct ReplayState {
+    pub resources: Resources,
+    pub game: Game,
+    replay: Replay,
+    dealt: bool,
+    /// Moves on the table.
+    played: usize,
+    playing: bool,
+    speed: f32,
+    /// Replay time since the last move.
+    clock: Duration,
+    /// Whether the replay holds up as a win, checked against the rules.
+    verdict: String,
+}
+
+impl ReplayState {
+    /// Time between the last move on the table and the next one.
+    fn pause(&self) -> Duration {
+        let last = match self.played {
+            0 => Duration::ZERO,
+            n => self.replay.moves[n - 1].0,
+        };
+        let next = self.replay.moves[self.played].0;
+        next.saturating_sub(last).min(MAX_PAUSE)
+    }
+
+    /// Shows the position after `n` moves at once.
+    fn seek(&mut self, n: usize) {
+        self.played = n.min(self.replay.moves.len());
+        self.clock = Duration::ZERO;
+        let board = self.replay.board(self.played);
+        self.game.set_position(&self.replay.deal, board.stacks());
+    }
+
+    fn step(&mut self) {
+        if let Some((_, m)) = self.replay.moves.get(self.played) {
+            self.game.animate_move(m);
+            self.played += 1;
+            self.clock = Duration::ZERO;
+        }
+    }
+
+    fn status(&self) -> String {
+        let state = match (self.playing, self.played == self.replay.moves.len()) {
+            (_, true) => self.verdict.clone(),
+            (true, false) => "playing".to_owned(),
+            (false, false) => "paused".to_owned(),
+        };
+        let seed = self
+            .replay
+            .seed
+            .map_or(String::new(), |s| format!("seed {}, ", s));
+        format!(
+            "Replay of {}{} moves in {:.0}s: move {}, {}x, {}",
+            seed,
+            self.replay.moves.len(),
+            self.replay.duration.as_secs_f64(),
+            self.played,
+            self.speed,
+            state
+        )
+    }
+}
+
+impl EventHandler for ReplayState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        let dt = ctx.time.delta().mul_f32(self.speed);
+        self.game.state.run_update(dt.as_secs_f32());
+        for event in self.game.state.drain_events() {
+            self.resources.play_event_sound(&event);
+        }
+        if !self.game.state.idle() {
+            return Ok(());
+        }
+
+        if !self.dealt {
+            // after the shuffle
+            self.game.animate_deal(&self.replay.deal);
+            self.dealt = true;
+        } else if self.playing && self.played < self.replay.moves.len() {
+            self.clock += dt;
+            if self.clock >= self.pause() {
+                self.step();
+            }
+        } else {
+            self.playing = false;
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = Canvas::from_frame(&ctx.gfx, None);
+        self.game
+            .state
+            .run_render(ctx, &mut self.resources, &mut canvas)?;
+
+        for (i, line) in [self.status(), HELP.to_owned()].iter().enumerate() {
+            let mut text = Text::new(line.as_str());
+            text.set_scale(PxScale::from(18.0));
+            let dim = text.dimensions(&ctx.gfx);
+            let pos = Point2::from([640.0 - dim.w / 2.0, 740.0 + i as f32 * 26.0]);
+            let backdrop = Mesh::new_rectangle(
+                &ctx.gfx,
+                DrawMode::fill(),
+                Rect::new(pos.x - 6.0, pos.y - 3.0, dim.w + 12.0, dim.h + 6.0),
+                graphics::Color::new(0.0, 0.0, 0.0, 0.7),
+            )?;
+            canvas.draw(&backdrop, DrawParam::new());
+            canvas.draw(&text, DrawParam::new().dest(pos));
+        }
+
+        canvas.finish(&mut ctx.gfx)?;
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        input: KeyInput,
+        _repeated: bool,
+    ) -> GameResult {
+        let PhysicalKey::Code(key) = input.event.physical_key else {
+            return Ok(());
+        };
+        if key == KeyCode::Escape {
+            ctx.request_quit();
+            return Ok(());
+        }
+        // everything else waits for the table to come to rest
+        if !self.dealt || !self.game.state.idle() {
+            return Ok(());
+        }
+        match key {
+            KeyCode::Space => self.playing = !self.playing,
+            KeyCode::ArrowRight => {
+                self.playing = false;
+                self.step();
+            }
+            KeyCode::ArrowLeft => {
+                self.playing = false;
+                self.seek(self.played.saturating_sub(1));
+            }
+            KeyCode::ArrowUp => self.speed = (self.speed * 2.0).min(MAX_SPEED),
+            KeyCode::ArrowDown => self.speed = (self.speed / 2.0).max(MIN_SPEED),
+            KeyCode::Home => self.seek(0),
+            KeyCode::End => self.seek(self.replay.moves.len()),
+            KeyCode::PageUp => self.seek(self.played.saturating_sub(SEEK_STEP)),
+            KeyCode::PageDown => self.seek(self.played + SEEK_STEP),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl From<WelcomeState> for ReplayState {
+    fn from(mut old: WelcomeState) -> ReplayState {
+        let replay = old.replay.take().expect("no replay to play");
+        let verdict = match replay.verify() {
+            Ok(()) => "verified win".to_owned(),
+            Err(VerifyError::NotClaimed(outcome)) => outcome.to_string(),
+            Err(e) => format!("not a verified win: {}", e),
+        };
+        ReplayState {
+            resources: old.resources,
+            game: old.game,
+            replay,
+            dealt: false,
+            played: 0,
+            playing: true,
+            speed: 1.0,
+            clock: Duration::ZERO,
+            verdict,
+        }
+    }
+}
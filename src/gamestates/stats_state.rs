@@ -0,0 +1,294 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+
+use ggez::event::*;
+use ggez::graphics::{self, Canvas, DrawMode, DrawParam, Drawable, Mesh, PxScale, Rect, Text};
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::input::mouse::MouseButton;
+use ggez::winit::keyboard::PhysicalKey;
+use ggez::{Context, GameResult};
+use log::warn;
+
+use solitaire_core::deal::Deal;
+use solitaire_core::replay::Replay;
+use solitaire_core::stats::{GameRecord, Histogram, Stats};
+
+use crate::game::Game;
+use crate::recorder::{load_replay, replay_path, saved_replays};
+use crate::resources::Resources;
+
+use super::welcome_state::WelcomeState;
+
+/// Games listed, newest first.
+const SHOWN_GAMES: usize = 50;
+const HISTOGRAM_BINS: usize = 10;
+const ROW_HEIGHT: f32 = 14.5;
+const LINK_COLOR: graphics::Color = graphics::Color::new(1.0, 0.85, 0.4, 1.0);
+
+/// What a click on a past game asks for.
+#[derive(Copy, Clone)]
+enum Pick {
+    Replay,
+    Play,
+}
+
+/// Totals, records, histograms and the last games, on top of the welcome table.
+pub struct StatsState {
+    pub resources: Resources,
+    pub game: Game,
+    /// Where the links of the last frame were drawn, with the index of their game in the
+    /// history.
+    links: Vec<(Rect, Pick, usize)>,
+    /// The start times of the games that can be watched, looked up on the first frame.
+    replays: Option<HashSet<u64>>,
+    /// The past game picked, to watch or to deal again.
+    pub replay: Option<Replay>,
+    pub deal: Option<(Option<u64>, Deal)>,
+}
+
+fn clock(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+fn text(s: &str, size: f32) -> Text {
+    let mut text = Text::new(s);
+    text.set_scale(PxScale::from(size));
+    text
+}
+
+fn summary(stats: &Stats) -> Vec<String> {
+    let mut lines = vec![
+        format!(
+            "Played {}, won {} ({:.0}%), given up {}",
+            stats.played,
+            stats.won,
+            stats.win_rate() * 100.0,
+            stats.gave_up
+        ),
+        format!(
+            "Winning streak {}, best streak {}",
+            stats.streak, stats.best_streak
+        ),
+    ];
+    if let (Some(fastest), Some(moves)) = (stats.fastest, stats.fewest_moves) {
+        lines.push(format!(
+            "Fastest win {}, fewest moves {}",
+            clock(fastest),
+            moves
+        ));
+    }
+    lines
+}
+
+/// The columns of a past game in the list, with their left edges.
+fn row(game: &GameRecord) -> [(f32, String); 4] {
+    let seed = game
+        .seed
+        .map_or("custom deal".to_owned(), |s| format!("seed {}", s));
+    [
+        (640.0, seed),
+        (860.0, game.outcome.to_string()),
+        (960.0, clock(game.duration)),
+        (1030.0, format!("{} moves", game.moves)),
+    ]
+}
+
+/// Draws the bars of a histogram into a box with the top left corner at `left`, `top`, with the
+/// start of the first and the end of the last range below.
+fn draw_histogram(
+    ctx: &Context,
+    canvas: &mut Canvas,
+    title: &str,
+    histogram: &Histogram,
+    label: impl Fn(f64) -> String,
+    left: f32,
+    top: f32,
+) -> GameResult {
+    const WIDTH: f32 = 520.0;
+    const HEIGHT: f32 = 120.0;
+    canvas.draw(&text(title, 18.0), DrawParam::new().dest([left, top]));
+    let top = top + 26.0;
+    if histogram.counts.iter().sum::<usize>() == 0 {
+        canvas.draw(
+            &text("no wins yet", 14.0),
+            DrawParam::new().dest([left, top]),
+        );
+        return Ok(());
+    }
+    let highest = histogram.counts.iter().copied().max().unwrap_or(0).max(1);
+    let bar = WIDTH / histogram.counts.len() as f32;
+    for (i, &count) in histogram.counts.iter().enumerate() {
+        if count == 0 {
+            continue;
+        }
+        let h = HEIGHT * count as f32 / highest as f32;
+        let rect = Rect::new(left + i as f32 * bar + 1.0, top + HEIGHT - h, bar - 2.0, h);
+        let mesh = Mesh::new_rectangle(&ctx.gfx, DrawMode::fill(), rect, LINK_COLOR)?;
+        canvas.draw(&mesh, DrawParam::new());
+        let n = text(&count.to_string(), 12.0);
+        let dim = n.dimensions(&ctx.gfx);
+        let pos = [rect.x + (rect.w - dim.w) / 2.0, rect.y - dim.h - 2.0];
+        canvas.draw(&n, DrawParam::new().dest(pos));
+    }
+    let axis = Rect::new(left, top + HEIGHT, WIDTH, 1.0);
+    let mesh = Mesh::new_rectangle(&ctx.gfx, DrawMode::fill(), axis, graphics::Color::WHITE)?;
+    canvas.draw(&mesh, DrawParam::new());
+
+    let end = histogram.min + histogram.width * histogram.counts.len() as f64;
+    let first = text(&label(histogram.min), 12.0);
+    canvas.draw(&first, DrawParam::new().dest([left, top + HEIGHT + 4.0]));
+    let last = text(&label(end), 12.0);
+    let dim = last.dimensions(&ctx.gfx);
+    canvas.draw(
+        &last,
+        DrawParam::new().dest([left + WIDTH - dim.w, top + HEIGHT + 4.0]),
+    );
+    Ok(())
+}
+
+impl EventHandler for StatsState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        let dt = ctx.time.delta().as_secs_f32();
+        self.game.state.run_update(dt);
+        for event in self.game.state.drain_events() {
+            self.resources.play_event_sound(&event);
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = Canvas::from_frame(&ctx.gfx, None);
+        self.game
+            .state
+            .run_render(ctx, &mut self.resources, &mut canvas)?;
+        let backdrop = Mesh::new_rectangle(
+            &ctx.gfx,
+            DrawMode::fill(),
+            Rect::new(0.0, 0.0, 1280.0, 806.0),
+            graphics::Color::new(0.0, 0.0, 0.0, 0.8),
+        )?;
+        canvas.draw(&backdrop, DrawParam::new());
+
+        let stats = self.resources.stats();
+        canvas.draw(
+            &text("Statistics", 32.0),
+            DrawParam::new().dest([40.0, 30.0]),
+        );
+        for (i, line) in summary(stats).iter().enumerate() {
+            let pos = [40.0, 90.0 + i as f32 * 26.0];
+            canvas.draw(&text(line, 18.0), DrawParam::new().dest(pos));
+        }
+
+        let times: Vec<f64> = stats.wins().map(|g| g.duration.as_secs_f64()).collect();
+        let moves: Vec<f64> = stats.wins().map(|g| g.moves as f64).collect();
+        draw_histogram(
+            ctx,
+            &mut canvas,
+            "Win times",
+            &Histogram::new(&times, HISTOGRAM_BINS),
+            |secs| clock(Duration::from_secs_f64(secs)),
+            40.0,
+            200.0,
+        )?;
+        draw_histogram(
+            ctx,
+            &mut canvas,
+            "Moves per win",
+            &Histogram::new(&moves, HISTOGRAM_BINS),
+            |n| format!("{:.0}", n),
+            40.0,
+            400.0,
+        )?;
+        let help = "Click replay to watch a game, play to deal it again. Escape goes back.";
+        canvas.draw(&text(help, 14.0), DrawParam::new().dest([40.0, 760.0]));
+
+        canvas.draw(
+            &text("Last games", 18.0),
+            DrawParam::new().dest([640.0, 30.0]),
+        );
+        self.links.clear();
+        let replays = self.replays.get_or_insert_with(|| saved_replays(ctx));
+        let newest = stats.history.iter().enumerate().rev().take(SHOWN_GAMES);
+        for (row_index, (i, game)) in newest.enumerate() {
+            let y = 60.0 + row_index as f32 * ROW_HEIGHT;
+            for (x, column) in row(game) {
+                canvas.draw(&text(&column, 12.0), DrawParam::new().dest([x, y]));
+            }
+            let mut x = 1130.0;
+            for (name, pick) in [("replay", Pick::Replay), ("play", Pick::Play)] {
+                let link = text(name, 12.0);
+                let dim = link.dimensions(&ctx.gfx);
+                // games without a replay keep the play link in its column
+                if matches!(pick, Pick::Play) || replays.contains(&game.started) {
+                    canvas.draw(&link, DrawParam::new().dest([x, y]).color(LINK_COLOR));
+                    self.links.push((Rect::new(x, y, dim.w, dim.h), pick, i));
+                }
+                x += dim.w + 16.0;
+            }
+        }
+        if stats.history.is_empty() {
+            canvas.draw(
+                &text("none yet", 14.0),
+                DrawParam::new().dest([640.0, 60.0]),
+            );
+        }
+
+        canvas.finish(&mut ctx.gfx)?;
+        Ok(())
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        if button != MouseButton::Left || self.game.state.busy() {
+            return Ok(());
+        }
+        let Some(&(_, pick, i)) = self.links.iter().find(|(r, _, _)| r.contains([x, y])) else {
+            return Ok(());
+        };
+        let game = &self.resources.stats().history[i];
+        match pick {
+            Pick::Replay => match load_replay(ctx, Path::new(&replay_path(game.started))) {
+                Ok(replay) => self.replay = Some(replay),
+                Err(e) => {
+                    warn!("no replay of this game: {}", e);
+                    return Ok(());
+                }
+            },
+            Pick::Play => self.deal = Some((game.seed, game.deal.clone())),
+        }
+        ctx.request_quit();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        input: KeyInput,
+        _repeated: bool,
+    ) -> GameResult {
+        if input.event.physical_key == PhysicalKey::Code(KeyCode::Escape) {
+            ctx.request_quit();
+        }
+        Ok(())
+    }
+}
+
+impl From<WelcomeState> for StatsState {
+    fn from(old: WelcomeState) -> StatsState {
+        StatsState {
+            resources: old.resources,
+            game: old.game,
+            links: Vec::new(),
+            replays: None,
+            replay: None,
+            deal: None,
+        }
+    }
+}
@@ -3,18 +3,32 @@ use ggez::mint::Point2;
 use ggez::{Context, GameResult};
 
 use super::giveup_state::GiveupState;
+use super::replay_state::ReplayState;
+use super::stats_state::StatsState;
 use super::victory_state::VictoryState;
 
 use crate::game::Game;
+use crate::recorder::latest_replay;
 use crate::resources::Resources;
 use ggez::graphics::{Canvas, DrawParam, Drawable};
+use ggez::input::keyboard::{KeyCode, KeyInput};
 use ggez::input::mouse::MouseButton;
+use ggez::winit::keyboard::PhysicalKey;
+use log::{info, warn};
+use solitaire_core::deal::Deal;
+use solitaire_core::replay::Replay;
 
 pub struct WelcomeState {
     pub resources: Resources,
     pub move_on: bool,
     pub game: Game,
     pub ready: bool,
+    /// Watch this instead of playing a new game.
+    pub replay: Option<Replay>,
+    /// Play this deal, with the seed that made it, instead of a new one.
+    pub deal: Option<(Option<u64>, Deal)>,
+    /// Show the statistics instead of playing.
+    pub stats: bool,
 }
 
 impl WelcomeState {
@@ -24,6 +38,9 @@ impl WelcomeState {
             move_on: false,
             game: Game::new(),
             ready: false,
+            replay: None,
+            deal: None,
+            stats: false,
         })
     }
 }
@@ -35,7 +52,10 @@ impl EventHandler for WelcomeState {
             self.ready = true;
         } else {
             let dt = ctx.time.delta().as_secs_f32();
-            self.game.state.run_update(dt, &mut self.resources);
+            self.game.state.run_update(dt);
+            for event in self.game.state.drain_events() {
+                self.resources.play_event_sound(&event);
+            }
         }
 
         Ok(())
@@ -54,6 +74,14 @@ impl EventHandler for WelcomeState {
         let pos = Point2::from([640.0 - dim.w / 2.0, 403.0 - dim.h / 2.0]);
         canvas.draw(text, DrawParam::new().dest(pos));
 
+        let text = self.resources.get_text(
+            ctx,
+            "Press R to watch the last game again, S for statistics.",
+        )?;
+        let dim = text.dimensions(&ctx.gfx);
+        let pos = Point2::from([640.0 - dim.w / 2.0, 483.0 - dim.h / 2.0]);
+        canvas.draw(text, DrawParam::new().dest(pos).scale([0.5, 0.5]));
+
         canvas.finish(&mut ctx.gfx)?;
         Ok(())
     }
@@ -71,6 +99,35 @@ impl EventHandler for WelcomeState {
         }
         Ok(())
     }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        input: KeyInput,
+        _repeated: bool,
+    ) -> GameResult<()> {
+        match input.event.physical_key {
+            PhysicalKey::Code(KeyCode::Escape) => ctx.request_quit(),
+            PhysicalKey::Code(KeyCode::KeyR) if !self.game.state.busy() => {
+                match latest_replay(ctx) {
+                    Ok(Some(replay)) => {
+                        self.replay = Some(replay);
+                        self.move_on = true;
+                        ctx.request_quit();
+                    }
+                    Ok(None) => info!("no replays yet"),
+                    Err(e) => warn!("could not load the last replay: {}", e),
+                }
+            }
+            PhysicalKey::Code(KeyCode::KeyS) if !self.game.state.busy() => {
+                self.stats = true;
+                self.move_on = true;
+                ctx.request_quit();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
 }
 
 impl From<VictoryState> for WelcomeState {
@@ -80,6 +137,9 @@ impl From<VictoryState> for WelcomeState {
             move_on: false,
             game: Game::new(),
             ready: true,
+            replay: None,
+            deal: None,
+            stats: false,
         }
     }
 }
@@ -91,6 +151,38 @@ impl From<GiveupState> for WelcomeState {
             move_on: false,
             game: Game::new(),
             ready: true,
+            replay: None,
+            deal: None,
+            stats: false,
+        }
+    }
+}
+
+impl From<ReplayState> for WelcomeState {
+    fn from(old: ReplayState) -> WelcomeState {
+        WelcomeState {
+            resources: old.resources,
+            move_on: false,
+            game: Game::new(),
+            ready: true,
+            replay: None,
+            deal: None,
+            stats: false,
+        }
+    }
+}
+
+/// Moves on to the game picked on the statistics screen, if there is one.
+impl From<StatsState> for WelcomeState {
+    fn from(old: StatsState) -> WelcomeState {
+        WelcomeState {
+            resources: old.resources,
+            move_on: old.replay.is_some() || old.deal.is_some(),
+            game: old.game,
+            ready: true,
+            replay: old.replay,
+            deal: old.deal,
+            stats: false,
         }
     }
 }
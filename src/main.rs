@@ -1,24 +1,116 @@
-mod ai;
 mod gamestates;
 //mod custom_audio;
+mod analysis;
+mod bot;
+mod console;
 mod cs;
+mod driver;
 mod game;
+mod recorder;
 mod resources;
-mod rules;
-mod types;
+mod stress;
+mod study;
 mod utils;
 
+use solitaire_core::{ai, deal, rules, types};
+
 use std::env;
 
 use ggez::{ContextBuilder, conf};
 
+use crate::bot::{Address, BotServer};
+use crate::console::Console;
 use crate::gamestates::GameWrapper;
 
 const SHENZHEN_PATH: &str = ".local/share/Steam/steamapps/common/SHENZHEN IO/Content/";
 
+const USAGE: &str =
+    "usage: solitaire [--console | --script FILE] [--listen unix:PATH | 127.0.0.1:PORT]
+       solitaire --stress GAMES [--seed SEED]";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    std::process::exit(2);
+}
+
 fn main() {
     env_logger::init();
 
+    let mut args = env::args().skip(1);
+    let mut console = None;
+    let mut bot = None;
+    let mut stress = None;
+    let mut seed = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--stress" if stress.is_none() => {
+                stress = Some(
+                    args.next()
+                        .and_then(|n| n.parse().ok())
+                        .unwrap_or_else(|| usage()),
+                )
+            }
+            "--seed" if seed.is_none() => {
+                seed = Some(
+                    args.next()
+                        .and_then(|n| n.parse().ok())
+                        .unwrap_or_else(|| usage()),
+                )
+            }
+            "--console" if console.is_none() => console = Some(Console::stdin()),
+            "--script" if console.is_none() => {
+                let path = args.next().unwrap_or_else(|| usage());
+                match Console::script(&path) {
+                    Ok(c) => console = Some(c),
+                    Err(e) => {
+                        eprintln!("{}: {}", path, e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--listen" if bot.is_none() => {
+                let addr: Address = match args.next().unwrap_or_else(|| usage()).parse() {
+                    Ok(addr) => addr,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        usage();
+                    }
+                };
+                match BotServer::listen(&addr) {
+                    Ok(server) => {
+                        eprintln!("listening on {}", addr);
+                        bot = Some(server);
+                    }
+                    Err(e) => {
+                        eprintln!("{}: {}", addr, e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            _ => usage(),
+        }
+    }
+
+    if let Some(games) = stress {
+        let seed = seed.unwrap_or_else(deal::Deal::random_seed);
+        println!("playing {} games from seed {}", games, seed);
+        match stress::run(games, seed) {
+            Ok(stats) => {
+                println!(
+                    "{} games, {} won, {} stuck, {} moves",
+                    stats.games, stats.won, stats.stuck, stats.moves
+                );
+                return;
+            }
+            Err(failure) => {
+                print!("{}", failure);
+                std::process::exit(1);
+            }
+        }
+    } else if seed.is_some() {
+        usage();
+    }
+
     let c = conf::Conf {
         window_mode: conf::WindowMode::default().dimensions(1280.0, 806.0),
         window_setup: conf::WindowSetup::default().title("Solitaire Clone"),
@@ -45,7 +137,7 @@ fn main() {
         .build()
         .unwrap();
 
-    let state = GameWrapper::new(&mut ctx).unwrap();
+    let state = GameWrapper::new(&mut ctx, console, bot).unwrap();
     ggez::event::run(ctx, event_loop, state).unwrap();
 
     #[cfg(feature = "profiling")]
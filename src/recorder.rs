@@ -0,0 +1,129 @@
+//! Records the game on the table as a replay, saved to `/replays` in the user data directory
+//! when the game ends.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use ggez::{Context, GameError, GameResult};
+
+use solitaire_core::board::Board;
+use solitaire_core::deal::Deal;
+use solitaire_core::replay::{Outcome, Replay};
+use solitaire_core::stats::GameRecord;
+
+use crate::cs::GameEvent;
+use crate::game::Game;
+
+pub const REPLAY_DIR: &str = "/replays";
+
+pub struct Recorder {
+    pub replay: Replay,
+    board: Board,
+    start: Instant,
+    // names the file, so replays sort by the time they started
+    started: SystemTime,
+}
+
+impl Recorder {
+    /// Starts recording a game that was just dealt. The seed is kept if it made the deal.
+    pub fn new(seed: Option<u64>, deal: Deal) -> Recorder {
+        let seed = seed.filter(|&s| Deal::from_seed(s) == deal);
+        Recorder {
+            board: Board::new(&deal),
+            replay: Replay::new(seed, deal),
+            start: Instant::now(),
+            started: SystemTime::now(),
+        }
+    }
+
+    /// Adds the move of a player event, false if it does not fit the recorded game, e.g.
+    /// because the table was set to another position.
+    pub fn record(&mut self, game: &Game, event: &GameEvent) -> bool {
+        if *event == GameEvent::GameWon {
+            self.end(Outcome::Won);
+            return true;
+        }
+        let Some(m) = game.event_move(event, &self.board) else {
+            return true;
+        };
+        if self.replay.outcome != Outcome::Unfinished || self.board.apply(&m).is_err() {
+            return false;
+        }
+        self.replay.moves.push((self.start.elapsed(), m));
+        true
+    }
+
+    fn end(&mut self, outcome: Outcome) {
+        if self.replay.outcome == Outcome::Unfinished {
+            self.replay.outcome = outcome;
+            self.replay.duration = self.start.elapsed();
+        }
+    }
+
+    /// Saves the replay, unless not a single move was made, and returns the game for the
+    /// statistics. A won game stays won.
+    pub fn save(mut self, ctx: &mut Context, outcome: Outcome) -> GameResult<Option<GameRecord>> {
+        if self.replay.moves.is_empty() {
+            return Ok(None);
+        }
+        self.end(outcome);
+        let millis = self
+            .started
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        // ggez only writes to the config directory, the data directory is read through it
+        let dir = ctx
+            .fs
+            .user_data_dir()
+            .join(REPLAY_DIR.trim_start_matches('/'));
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(format!("{}.txt", millis)), self.replay.to_string())?;
+        Ok(Some(GameRecord {
+            started: millis,
+            seed: self.replay.seed,
+            deal: self.replay.deal,
+            outcome: self.replay.outcome,
+            duration: self.replay.duration,
+            moves: self.replay.moves.len(),
+        }))
+    }
+}
+
+/// The replay file of the game that started at `millis`.
+pub fn replay_path(millis: u64) -> String {
+    format!("{}/{}.txt", REPLAY_DIR, millis)
+}
+
+pub fn load_replay(ctx: &Context, path: &Path) -> GameResult<Replay> {
+    let mut text = String::new();
+    ctx.fs.open(path)?.read_to_string(&mut text)?;
+    text.parse()
+        .map_err(|e| GameError::ResourceLoadError(format!("{}: {}", path.display(), e)))
+}
+
+/// The start times of the games whose replays were saved.
+pub fn saved_replays(ctx: &Context) -> HashSet<u64> {
+    ctx.fs
+        .read_dir(REPLAY_DIR)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|p| p.file_stem()?.to_str()?.parse().ok())
+        .collect()
+}
+
+/// The replay that was saved last, if there is one.
+pub fn latest_replay(ctx: &Context) -> GameResult<Option<Replay>> {
+    let latest = ctx
+        .fs
+        .read_dir(REPLAY_DIR)?
+        .into_iter()
+        .filter_map(|p| {
+            let millis: u128 = p.file_stem()?.to_str()?.parse().ok()?;
+            Some((millis, p))
+        })
+        .max();
+    latest.map(|(_, p)| load_replay(ctx, &p)).transpose()
+}
@@ -1,23 +1,23 @@
 use std::collections::HashMap;
 use std::collections::hash_map::Entry;
-use std::io::{Read, Write};
+use std::fs;
+use std::io::Write;
 
-use crate::types::{ButtonState, Color};
+use crate::cs::GameEvent;
+use crate::types::{ButtonState, Color, Sounds};
 use ggez::audio::{SoundSource, Source};
 use ggez::graphics::{FontData, Image, PxScale, Text, TextFragment};
 use ggez::*;
+use log::warn;
+use solitaire_core::replay::Outcome;
+use solitaire_core::stats::{GameRecord, Stats};
 
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub enum Sounds {
-    None,
-    //Pickup,
-    Place,
-    Sweep,
-    Deal,
-}
+const STATS_FILE: &str = "stats.txt";
+/// The win counter of older versions, migrated to the statistics.
+const WINS_FILE: &str = "wins.txt";
 
 pub struct Resources {
-    wins: u32,
+    stats: Stats,
     pub table_image: Image,
     pub card_front: Image,
     pub card_back: Image,
@@ -167,7 +167,7 @@ impl Resources {
         );
 
         let r = Resources {
-            wins: Resources::load_wins(ctx)?,
+            stats: Resources::load_stats(ctx)?,
             table_image: Image::from_path(&ctx.gfx, "/textures/solitaire/table_large.png")?,
             card_front: Image::from_path(&ctx.gfx, "/textures/solitaire/card_front.png")?,
             card_back: Image::from_path(&ctx.gfx, "/textures/solitaire/card_back.png")?,
@@ -204,7 +204,7 @@ impl Resources {
     pub fn play_sound(&mut self, sound: Sounds) {
         match sound {
             Sounds::None => return,
-            //Sounds::Pickup => self.pickup_sound.play(),
+            Sounds::Pickup => self.pickup_sound.play(),
             Sounds::Place => self.place_sound.play(),
             Sounds::Deal => self.deal_sound.play(),
             Sounds::Sweep => self.sweep_sound.play(),
@@ -212,30 +212,102 @@ impl Resources {
         .unwrap();
     }
 
-    pub fn wins(&self) -> u32 {
-        self.wins
+    /// Plays the sound that goes with a game event, if there is one.
+    pub fn play_event_sound(&mut self, event: &GameEvent) {
+        let sound = match *event {
+            GameEvent::CardPickedUp { .. } => Sounds::Pickup,
+            GameEvent::CardsDropped { .. } | GameEvent::DropRejected { .. } => Sounds::Place,
+            GameEvent::Sound(sound) => sound,
+            _ => Sounds::None,
+        };
+        self.play_sound(sound);
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Adds a finished game to the statistics and saves them. Failing to save is not worth
+    /// ending the game for, it is only logged.
+    pub fn record_game(&mut self, ctx: &Context, game: GameRecord) {
+        self.stats.record(game);
+        self.save_stats(ctx);
+    }
+
+    /// Counts a game that was not recorded, e.g. one won after restoring a saved position.
+    pub fn count_game(&mut self, ctx: &Context, outcome: Outcome) {
+        self.stats.count(outcome, None, None);
+        self.save_stats(ctx);
+    }
+
+    fn save_stats(&self, ctx: &Context) {
+        if let Err(e) = Resources::store_stats(ctx, &self.stats) {
+            warn!("could not save the statistics: {}", e);
+        }
     }
-    pub fn add_win(&mut self, ctx: &mut Context) {
-        self.wins += 1;
-        self.store_wins(ctx, self.wins);
+
+    /// Loads the statistics, from the win counter of older versions on the first run. A file
+    /// that cannot be read is moved aside and the statistics start over.
+    fn load_stats(ctx: &Context) -> GameResult<Stats> {
+        let bytes = match ctx.fs.read(format!("/{}", STATS_FILE)) {
+            Ok(bytes) => bytes,
+            Err(GameError::ResourceNotFound(_, _)) => {
+                let stats = Resources::migrate_wins(ctx)?;
+                if let Err(e) = Resources::store_stats(ctx, &stats) {
+                    warn!("could not save the statistics: {}", e);
+                }
+                return Ok(stats);
+            }
+            Err(e) => return Err(e),
+        };
+        let stats = String::from_utf8(bytes)
+            .map_err(|e| e.to_string())
+            .and_then(|text| text.parse::<Stats>().map_err(|e| e.to_string()));
+        match stats {
+            Ok(stats) => Ok(stats),
+            Err(e) => {
+                let dir = ctx.fs.user_data_dir();
+                let bad = dir.join(format!("{}.bad", STATS_FILE));
+                warn!("{}: {}, moved to {}", STATS_FILE, e, bad.display());
+                if let Err(e) = fs::rename(dir.join(STATS_FILE), &bad) {
+                    warn!("could not move {} to {}: {}", STATS_FILE, bad.display(), e);
+                }
+                Ok(Stats::default())
+            }
+        }
     }
 
-    fn load_wins(ctx: &mut Context) -> GameResult<u32> {
-        match ctx.fs.open("/wins.txt") {
-            Ok(mut f) => {
-                let mut string = String::new();
-                f.read_to_string(&mut string)?;
-                let n: u32 = string.parse().unwrap();
-                Ok(n)
+    fn migrate_wins(ctx: &Context) -> GameResult<Stats> {
+        let bytes = match ctx.fs.read(format!("/{}", WINS_FILE)) {
+            Ok(bytes) => bytes,
+            Err(GameError::ResourceNotFound(_, _)) => return Ok(Stats::default()),
+            Err(e) => return Err(e),
+        };
+        let text = String::from_utf8_lossy(&bytes);
+        match text.trim().parse() {
+            Ok(wins) => Ok(Stats::from_wins(wins)),
+            Err(_) => {
+                warn!(
+                    "{}: invalid win count '{}', ignored",
+                    WINS_FILE,
+                    text.trim()
+                );
+                Ok(Stats::default())
             }
-            Err(GameError::ResourceNotFound(_, _)) => Ok(0),
-            Err(e) => Err(e),
         }
     }
 
-    pub fn store_wins(&self, ctx: &mut Context, n: u32) {
-        let mut f = ctx.fs.create("/wins.txt").unwrap();
-        f.write_all(format!("{}", n).as_bytes()).unwrap();
+    /// Writes the statistics to a new file and renames it over the old one, so they are never
+    /// left half written.
+    fn store_stats(ctx: &Context, stats: &Stats) -> GameResult {
+        let dir = ctx.fs.user_data_dir();
+        fs::create_dir_all(dir)?;
+        let tmp = dir.join(format!("{}.new", STATS_FILE));
+        let mut f = fs::File::create(&tmp)?;
+        f.write_all(stats.to_string().as_bytes())?;
+        f.sync_all()?;
+        fs::rename(tmp, dir.join(STATS_FILE))?;
+        Ok(())
     }
 }
 
@@ -0,0 +1,249 @@
+//! Random play through the real game (`--stress GAMES`), without a window.
+//!
+//! Every game is dealt from its seed and played with random moves, mostly legal ones plus the
+//! occasional drag the rules reject. Moves are mouse gestures at the computed card and button
+//! positions, time advances in steps of a size picked per game, and the invariants of the world
+//! are checked after every update. A failing game is shrunk to the fewest moves that still fail
+//! and reported as a console script.
+
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use solitaire_core::board::{Board, N_STACKS};
+use solitaire_core::deal::{Deal, SplitMix64};
+use solitaire_core::notation::{color_name, stack_name};
+use solitaire_core::rules::Move;
+
+use crate::driver::{Driver, Outcome};
+use crate::game::Game;
+
+/// Moves per game before giving up on it.
+const MAX_MOVES: usize = 200;
+/// Updates per move before the table must be at rest.
+const MAX_STEPS: usize = 2000;
+const TIME_STEPS: [f32; 4] = [0.005, 0.016, 0.05, 0.3];
+/// One move in this many is a random drag, which the rules usually reject.
+const RANDOM_DRAGS: usize = 5;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Stats {
+    pub games: usize,
+    pub won: usize,
+    pub stuck: usize,
+    pub moves: usize,
+}
+
+/// A game that broke, with the moves needed to break it.
+#[derive(Debug)]
+pub struct Failure {
+    pub seed: u64,
+    pub dt: f32,
+    pub moves: Vec<Move>,
+    pub message: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# game {} failed: {}", self.seed, self.message)?;
+        writeln!(f, "# time step {}s, replay with --script", self.dt)?;
+        writeln!(f, "deal {}", self.seed)?;
+        for m in &self.moves {
+            match *m {
+                Move::Cards(t, s, n) => {
+                    writeln!(f, "move {} {} {}", stack_name(s), stack_name(t), n)?
+                }
+                Move::Button(c, _, _) => writeln!(f, "collapse {}", color_name(c))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs the game until it is at rest, checking the invariants after every update.
+fn settle(game: &mut Game, driver: &mut Driver, dt: f32) -> Result<Option<Outcome>, String> {
+    for _ in 0..MAX_STEPS {
+        if let Some(outcome) = driver.update(game) {
+            return Ok(Some(outcome));
+        }
+        if driver.ready(game) {
+            return Ok(None);
+        }
+        game.state.run_update(dt);
+        game.state.drain_events();
+        let violations = game.state.invariant_violations();
+        if !violations.is_empty() {
+            return Err(violations.join(", "));
+        }
+    }
+    Err("the table does not come to rest".to_owned())
+}
+
+fn play(game: &mut Game, driver: &mut Driver, m: Move, dt: f32) -> Result<(), String> {
+    match m {
+        Move::Cards(t, s, n) => driver.move_cards(game, s, t, n),
+        Move::Button(c, _, _) => driver.collapse(game, c),
+    }
+    match settle(game, driver, dt)? {
+        Some(Outcome::Mismatch(_)) => {
+            Err(format!("the table does not match the rules after {}", m))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn new_game(seed: u64, driver: &mut Driver, dt: f32) -> Result<Game, String> {
+    let mut game = Game::new();
+    driver.deal(&mut game, Deal::from_seed(seed));
+    settle(&mut game, driver, dt)?;
+    Ok(game)
+}
+
+/// Panics of the systems, including broken invariants in debug builds, become errors.
+fn catch<T>(f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => Err(
+            match (
+                payload.downcast_ref::<&str>(),
+                payload.downcast_ref::<String>(),
+            ) {
+                (Some(s), _) => format!("panic: {}", s),
+                (_, Some(s)) => format!("panic: {}", s),
+                _ => "panic".to_owned(),
+            },
+        ),
+    }
+}
+
+/// Plays the moves, the error of the first one that fails if any does.
+fn replay(seed: u64, dt: f32, moves: &[Move]) -> Result<(), String> {
+    catch(|| {
+        let mut driver = Driver::default();
+        let mut game = new_game(seed, &mut driver, dt)?;
+        for &m in moves {
+            if let Move::Cards(_, s, n) = m
+                && game.export()[s].len() < n
+            {
+                continue; // shrinking took away the cards of this drag
+            }
+            play(&mut game, &mut driver, m, dt)?;
+        }
+        Ok(())
+    })
+}
+
+/// Drops every move that is not needed for the game to fail.
+fn shrink(mut failure: Failure) -> Failure {
+    let mut i = 0;
+    while i < failure.moves.len() {
+        let mut moves = failure.moves.clone();
+        moves.remove(i);
+        match replay(failure.seed, failure.dt, &moves) {
+            Err(message) => {
+                failure.moves = moves;
+                failure.message = message;
+            }
+            Ok(()) => i += 1,
+        }
+    }
+    failure
+}
+
+fn random_move(board: &Board, rng: &mut SplitMix64) -> Option<Move> {
+    if rng.below(RANDOM_DRAGS) == 0 {
+        let s = rng.below(N_STACKS);
+        let len = board.stacks()[s].len();
+        if len > 0 {
+            return Some(Move::Cards(rng.below(N_STACKS), s, 1 + rng.below(len)));
+        }
+    }
+    let moves = board.legal_moves();
+    if moves.is_empty() {
+        return None;
+    }
+    Some(moves[rng.below(moves.len())])
+}
+
+/// Plays one game, returns whether it was won.
+pub fn play_game(seed: u64, stats: &mut Stats) -> Result<bool, Failure> {
+    let mut rng = SplitMix64::new(seed);
+    let dt = TIME_STEPS[rng.below(TIME_STEPS.len())];
+    let mut moves = Vec::new();
+
+    let result = catch(|| {
+        let mut driver = Driver::default();
+        let mut game = new_game(seed, &mut driver, dt)?;
+        for _ in 0..MAX_MOVES {
+            let board = Board::from_stacks(game.export());
+            if board.is_won() {
+                return Ok(true);
+            }
+            let Some(m) = random_move(&board, &mut rng) else {
+                return Ok(false);
+            };
+            moves.push(m);
+            play(&mut game, &mut driver, m, dt)?;
+        }
+        Ok(false)
+    });
+
+    stats.games += 1;
+    stats.moves += moves.len();
+    match result {
+        Ok(true) => {
+            stats.won += 1;
+            Ok(true)
+        }
+        Ok(false) => {
+            stats.stuck += 1;
+            Ok(false)
+        }
+        Err(message) => Err(shrink(Failure {
+            seed,
+            dt,
+            moves,
+            message,
+        })),
+    }
+}
+
+/// Plays `games` games with the seeds following `seed`, stopping at the first failure.
+pub fn run(games: usize, seed: u64) -> Result<Stats, Failure> {
+    let mut stats = Stats::default();
+    for i in 0..games as u64 {
+        play_game(seed.wrapping_add(i), &mut stats)?;
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_games() {
+        let stats = run(3, 1000).unwrap_or_else(|f| panic!("{}", f));
+        assert_eq!(stats.games, 3);
+        assert_eq!(stats.won + stats.stuck, 3);
+        assert!(stats.moves > 0);
+    }
+
+    #[test]
+    fn reproduction() {
+        let failure = Failure {
+            seed: 12,
+            dt: 0.05,
+            moves: vec![
+                Move::Cards(4, 0, 2),
+                Move::Button(solitaire_core::types::Color::Red, 8, [0; 4]),
+            ],
+            message: "broken".to_owned(),
+        };
+        let text = failure.to_string();
+        assert_eq!(
+            text.lines().skip(2).collect::<Vec<_>>(),
+            ["deal 12", "move 1 5 2", "collapse r"]
+        );
+        assert!(replay(12, 0.05, &[]).is_ok());
+    }
+}
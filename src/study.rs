@@ -0,0 +1,116 @@
+//! The variation tree of the game on the table. Every move the player makes is recorded at the
+//! node the table shows, so going back and playing something else starts a new variation.
+
+use solitaire_core::deal::Deal;
+use solitaire_core::notation::NotationError;
+use solitaire_core::variations::{NodeId, ROOT, Variations};
+
+use crate::cs::GameEvent;
+use crate::game::Game;
+
+pub struct Study {
+    pub tree: Variations,
+    /// The node the table shows.
+    pub node: NodeId,
+}
+
+impl Study {
+    pub fn new(deal: Deal) -> Study {
+        Study {
+            tree: Variations::new(deal),
+            node: ROOT,
+        }
+    }
+
+    /// Loads a saved tree and shows its root.
+    pub fn load(text: &str, game: &mut Game) -> Result<Study, NotationError> {
+        let mut study = Study {
+            tree: text.parse()?,
+            node: ROOT,
+        };
+        study.show(ROOT, game);
+        Ok(study)
+    }
+
+    /// Adds the move of a player event at the current node, false if the table does not show
+    /// the current node any more.
+    pub fn record(&mut self, game: &Game, event: &GameEvent) -> bool {
+        let board = self.tree.board(self.node);
+        let Some(m) = game.event_move(event, &board) else {
+            return true;
+        };
+        match self.tree.play(self.node, m) {
+            Ok(node) => {
+                self.node = node;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Puts the position of a node onto the table.
+    pub fn show(&mut self, node: NodeId, game: &mut Game) {
+        self.node = node;
+        let board = self.tree.board(node);
+        game.set_position(self.tree.deal(), board.stacks());
+    }
+
+    pub fn back(&mut self, game: &mut Game) {
+        if let Some(parent) = self.tree.parent(self.node) {
+            self.show(parent, game);
+        }
+    }
+
+    /// Follows the main line.
+    pub fn forward(&mut self, game: &mut Game) {
+        if let Some(&child) = self.tree.children(self.node).first() {
+            self.show(child, game);
+        }
+    }
+
+    /// Switches to the next (`step` 1) or previous (-1) variation of the last move.
+    pub fn switch(&mut self, step: isize, game: &mut Game) {
+        let Some(parent) = self.tree.parent(self.node) else {
+            return;
+        };
+        let siblings = self.tree.children(parent);
+        let i = siblings.iter().position(|&c| c == self.node).unwrap();
+        let n = siblings.len() as isize;
+        let next = siblings[(i as isize + step).rem_euclid(n) as usize];
+        self.show(next, game);
+    }
+
+    /// What the analysis view shows about the tree: the last moves of the line, the variations
+    /// of the last move, the continuations and the comment.
+    pub fn summary(&self) -> Vec<String> {
+        let line = self.tree.line(self.node);
+        let shown: Vec<_> = line
+            .iter()
+            .skip(line.len().saturating_sub(8))
+            .map(|m| m.to_string())
+            .collect();
+        let more = if line.len() > shown.len() { "... " } else { "" };
+        let mut lines = vec![format!("move {}: {}{}", line.len(), more, shown.join(" "))];
+
+        if let Some(parent) = self.tree.parent(self.node) {
+            let siblings = self.tree.children(parent);
+            if siblings.len() > 1 {
+                let i = siblings.iter().position(|&c| c == self.node).unwrap();
+                lines.push(format!("variation {} of {}", i + 1, siblings.len()));
+            }
+        }
+        let next: Vec<_> = self
+            .tree
+            .children(self.node)
+            .iter()
+            .map(|&c| self.tree.last_move(c).unwrap().to_string())
+            .collect();
+        if !next.is_empty() {
+            lines.push(format!("next: {}", next.join(" | ")));
+        }
+        if !self.tree.comment(self.node).is_empty() {
+            lines.push(format!("{{{}}}", self.tree.comment(self.node)));
+        }
+        lines
+    }
+}
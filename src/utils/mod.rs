@@ -1,2 +1 @@
-pub mod all;
 pub mod bbox;